@@ -0,0 +1,527 @@
+use crate::{
+    combined_query, parse_combined, parse_setpoint, parse_triple, ChMeas, Channel, Error, Meas,
+    MeasureMode, Model, ModelInfo, Result, ScpiError, Setpoint, Simulator, State, Transport,
+    ERROR_QUEUE_DEPTH,
+};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::task::JoinHandle;
+
+/// Async counterpart of [`Transport`].
+///
+/// `read` must be cancellation safe: if its future is dropped, bytes already
+/// received are kept for the next call rather than lost.
+pub trait AsyncTransport: Send {
+    fn write(&mut self, command: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Reads one response line with the terminator stripped.
+    fn read(&mut self) -> impl Future<Output = Result<String>> + Send;
+
+    fn clear(&mut self) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// SCPI over a TCP socket driven by tokio.
+#[derive(Debug)]
+pub struct AsyncTcpTransport {
+    stream: TcpStream,
+    pending: Vec<u8>,
+}
+
+impl AsyncTcpTransport {
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            pending: Vec::new(),
+        })
+    }
+}
+
+impl AsyncTransport for AsyncTcpTransport {
+    async fn write(&mut self, command: &str) -> Result<()> {
+        self.stream
+            .write_all(format!("{}\n", command).as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    async fn read(&mut self) -> Result<String> {
+        let mut chunk = [0u8; 256];
+        loop {
+            if let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+                let line = self.pending.drain(..=end).collect::<Vec<u8>>();
+                let line = String::from_utf8_lossy(&line);
+                return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+            }
+            // `read` is cancellation safe, and anything it returned is already in `pending`.
+            let n = self.stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+            self.pending.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    async fn clear(&mut self) -> Result<()> {
+        self.pending.clear();
+        let mut chunk = [0u8; 256];
+        loop {
+            match self.stream.try_read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(_) => continue,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Runs a blocking [`Transport`], such as a VISA session, on tokio's blocking
+/// thread pool so it doesn't stall the executor.
+#[derive(Debug)]
+pub struct BlockingTransport<T> {
+    inner: Arc<Mutex<T>>,
+    /// A read whose caller gave up; the line it returns goes to the next `read`.
+    pending_read: Option<JoinHandle<Result<String>>>,
+}
+
+impl<T: Transport + Send + 'static> BlockingTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            pending_read: None,
+        }
+    }
+
+    fn spawn<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut T) -> Result<R> + Send + 'static,
+    ) -> JoinHandle<Result<R>> {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut inner = inner.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut inner)
+        })
+    }
+}
+
+fn join_error(e: tokio::task::JoinError) -> Error {
+    Error::Io(std::io::Error::other(e))
+}
+
+impl<T: Transport + Send + 'static> AsyncTransport for BlockingTransport<T> {
+    async fn write(&mut self, command: &str) -> Result<()> {
+        let command = command.to_string();
+        self.spawn(move |t| t.write(&command))
+            .await
+            .map_err(join_error)?
+    }
+
+    async fn read(&mut self) -> Result<String> {
+        if self.pending_read.is_none() {
+            self.pending_read = Some(self.spawn(|t| t.read()));
+        }
+        // Awaiting the handle by reference keeps it, and the line, if this future is dropped.
+        let handle = self.pending_read.as_mut().expect("read was just spawned");
+        let result = handle.await;
+        self.pending_read = None;
+        result.map_err(join_error)?
+    }
+
+    async fn clear(&mut self) -> Result<()> {
+        self.pending_read = None;
+        self.spawn(|t| t.clear()).await.map_err(join_error)?
+    }
+}
+
+/// [`Simulator`] behind the async API.
+#[derive(Debug)]
+pub struct AsyncSimulator(pub Simulator);
+
+impl AsyncSimulator {
+    pub fn new(model: Model) -> Self {
+        Self(Simulator::new(model))
+    }
+}
+
+impl AsyncTransport for AsyncSimulator {
+    async fn write(&mut self, command: &str) -> Result<()> {
+        self.0.write(command)
+    }
+
+    async fn read(&mut self) -> Result<String> {
+        self.0.read()
+    }
+
+    async fn clear(&mut self) -> Result<()> {
+        self.0.clear()
+    }
+}
+
+/// Async version of [`Keithley2230`](crate::Keithley2230).
+///
+/// Every bus operation is bounded by [`timeout`](Self::set_timeout). A query
+/// whose read was dropped, by cancellation or timeout, leaves its response
+/// owed; it is read and discarded before the next query so answers never get
+/// paired with the wrong command. An owed response that never turns up is
+/// given up on with [`clear`](Self::clear).
+pub struct AsyncKeithley2230<T: AsyncTransport> {
+    pub inner: T,
+    info: ModelInfo,
+    check_errors: bool,
+    timeout: Duration,
+    owed_responses: usize,
+}
+
+impl AsyncKeithley2230<AsyncTcpTransport> {
+    pub async fn new_tcp<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let transport = AsyncTcpTransport::connect(addr).await?;
+        Self::with_transport(transport).await
+    }
+}
+
+impl<T: AsyncTransport> AsyncKeithley2230<T> {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Wraps an open transport, identifying the unit with `*IDN?`.
+    pub async fn with_transport(mut inner: T) -> Result<Self> {
+        let timeout = Self::DEFAULT_TIMEOUT;
+        let cmd = crate::IDENTIFY;
+        Self::with_timeout(timeout, inner.write(cmd)).await?;
+        let idn = Self::with_timeout(timeout, inner.read()).await?;
+        Ok(Self {
+            inner,
+            info: ModelInfo::from_idn(&idn)?,
+            check_errors: false,
+            timeout,
+            owed_responses: 0,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    pub fn model_info(&self) -> &ModelInfo {
+        &self.info
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_error_checking(&mut self, enabled: bool) {
+        self.check_errors = enabled;
+    }
+
+    async fn with_timeout<R>(timeout: Duration, f: impl Future<Output = Result<R>>) -> Result<R> {
+        tokio::time::timeout(timeout, f)
+            .await
+            .map_err(|_| Error::Timeout())?
+    }
+
+    /// Discards pending input and forgets responses still owed by dropped queries.
+    pub async fn clear(&mut self) -> Result<()> {
+        self.owed_responses = 0;
+        Self::with_timeout(self.timeout, self.inner.clear()).await
+    }
+
+    async fn settle(&mut self) -> Result<()> {
+        while self.owed_responses > 0 {
+            match tokio::time::timeout(self.timeout, self.inner.read()).await {
+                Ok(_) => self.owed_responses -= 1,
+                // The response is lost; resync rather than wait for it forever.
+                Err(_) => self.clear().await?,
+            }
+        }
+        Ok(())
+    }
+
+    async fn query(&mut self, cmd: &str) -> Result<String> {
+        self.settle().await?;
+        Self::with_timeout(self.timeout, self.inner.write(cmd)).await?;
+        // Only a dropped read leaves the response owed; one that returned,
+        // even with an error, has settled it.
+        self.owed_responses += 1;
+        let response = tokio::time::timeout(self.timeout, self.inner.read())
+            .await
+            .map_err(|_| Error::Timeout())?;
+        self.owed_responses -= 1;
+        response
+    }
+
+    async fn command(&mut self, cmd: &str) -> Result<()> {
+        self.settle().await?;
+        Self::with_timeout(self.timeout, self.inner.write(cmd)).await?;
+        if self.check_errors {
+            if let Some(error) = self.read_error_queue().await?.into_iter().next() {
+                return Err(Error::Instrument(error));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn read_error_queue(&mut self) -> Result<Vec<ScpiError>> {
+        let mut errors = Vec::new();
+        for _ in 0..ERROR_QUEUE_DEPTH {
+            let error = ScpiError::from_str(&self.query("SYST:ERR?").await?)?;
+            if error.code == 0 {
+                break;
+            }
+            errors.push(error);
+        }
+        Ok(errors)
+    }
+
+    fn check_channel(&self, ch: Channel) -> Result<()> {
+        if self.info.has_channel(ch) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedChannel(ch))
+        }
+    }
+
+    pub async fn set_channel(&mut self, ch: Channel, v: f32, i: f32) -> Result<()> {
+        self.info.validate_setpoint(ch, v, i)?;
+        self.command(&format!("APPL {}, {}, {}", ch, v, i)).await
+    }
+
+    pub async fn get_setpoint(&mut self, ch: Channel) -> Result<Setpoint> {
+        self.check_channel(ch)?;
+        let cmd = format!("APPL? {}", ch);
+        let response = self.query(&cmd).await?;
+        parse_setpoint(&cmd, &response)
+    }
+
+    pub async fn enable_output(&mut self, state: State) -> Result<()> {
+        self.command(&format!("OUTP:ENAB {}", state)).await
+    }
+
+    pub async fn enable_channel(&mut self, ch: Channel, state: State) -> Result<()> {
+        let prev_ch = self.get_channel().await?;
+        self.select_channel(ch).await?;
+        let result = self.command(&format!("CHAN:OUTP {}", state)).await;
+        self.select_channel(prev_ch).await?;
+        result
+    }
+
+    pub async fn get_channel(&mut self) -> Result<Channel> {
+        let ch = self.query("INST?").await?;
+        Ok(Channel::from_str(&ch)?)
+    }
+
+    pub async fn select_channel(&mut self, ch: Channel) -> Result<()> {
+        self.check_channel(ch)?;
+        self.command(&format!("INST {}", ch)).await
+    }
+
+    pub async fn front_panel_ctrl(&mut self) -> Result<()> {
+        self.command("SYST:LOC").await
+    }
+
+    pub async fn remote_ctrl(&mut self) -> Result<()> {
+        self.command("SYST:REM").await
+    }
+
+    async fn query_triple(&mut self, cmd: &str) -> Result<(f32, f32, f32)> {
+        let response = self.query(cmd).await?;
+        parse_triple(cmd, &response, self.info.channels.len())
+    }
+
+    pub async fn read_i(&mut self) -> Result<(f32, f32, f32)> {
+        self.query_triple("FETC:CURR? ALL").await
+    }
+
+    pub async fn read_v(&mut self) -> Result<(f32, f32, f32)> {
+        self.query_triple("FETC:VOLT? ALL").await
+    }
+
+    pub async fn read_p(&mut self) -> Result<(f32, f32, f32)> {
+        self.query_triple("FETC:POW? ALL").await
+    }
+
+    pub async fn read_all(&mut self) -> Result<Meas> {
+        let v = self.read_v().await?;
+        let i = self.read_i().await?;
+        let p = self.read_p().await?;
+
+        Ok(Meas {
+            ch1: ChMeas::new(v.0, i.0, p.0),
+            ch2: ChMeas::new(v.1, i.1, p.1),
+            ch3: ChMeas::new(v.2, i.2, p.2),
+        })
+    }
+
+    pub async fn read_all_combined(&mut self, mode: MeasureMode) -> Result<Meas> {
+        let cmd = combined_query(mode);
+        let response = self.query(&cmd).await?;
+        parse_combined(&cmd, &response, self.info.channels.len())
+    }
+
+    pub async fn set_paralel(&mut self, state: State) -> Result<()> {
+        self.command(&format!("OUT:PAR {}", state)).await
+    }
+
+    pub async fn set_series(&mut self, state: State) -> Result<()> {
+        self.command(&format!("OUT:SER {}", state)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulator whose responses take `delay` to arrive, or never while `stalled`.
+    struct Slow {
+        sim: AsyncSimulator,
+        delay: Duration,
+        stalled: bool,
+        clears: usize,
+    }
+
+    impl AsyncTransport for Slow {
+        async fn write(&mut self, command: &str) -> Result<()> {
+            self.sim.write(command).await
+        }
+
+        async fn read(&mut self) -> Result<String> {
+            if self.stalled {
+                std::future::pending::<()>().await;
+            }
+            // Nothing is taken from the simulator until the delay is over, so a
+            // dropped read loses nothing.
+            tokio::time::sleep(self.delay).await;
+            self.sim.read().await
+        }
+
+        async fn clear(&mut self) -> Result<()> {
+            self.stalled = false;
+            self.clears += 1;
+            self.sim.clear().await
+        }
+    }
+
+    /// Blocking counterpart of [`Slow`] for [`BlockingTransport`].
+    struct SlowBlocking {
+        sim: Simulator,
+        delay: Duration,
+    }
+
+    impl Transport for SlowBlocking {
+        fn write(&mut self, command: &str) -> Result<()> {
+            self.sim.write(command)
+        }
+
+        fn read(&mut self) -> Result<String> {
+            std::thread::sleep(self.delay);
+            self.sim.read()
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.sim.clear()
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    async fn supply() -> AsyncKeithley2230<Slow> {
+        let slow = Slow {
+            sim: AsyncSimulator::new(Model::K2230_30_1),
+            delay: Duration::ZERO,
+            stalled: false,
+            clears: 0,
+        };
+        let mut k = AsyncKeithley2230::with_transport(slow).await.unwrap();
+        k.set_timeout(TIMEOUT);
+        k.set_channel(Channel::CH1, 1.0, 0.1).await.unwrap();
+        k.set_channel(Channel::CH2, 2.0, 0.2).await.unwrap();
+        k
+    }
+
+    #[tokio::test]
+    async fn late_response_is_discarded() {
+        let mut k = supply().await;
+        k.inner.delay = TIMEOUT * 2;
+        assert!(matches!(
+            k.get_setpoint(Channel::CH1).await,
+            Err(Error::Timeout())
+        ));
+
+        // CH1's answer arrives now and must not be taken for CH2's.
+        k.inner.delay = Duration::ZERO;
+        assert_eq!(
+            k.get_setpoint(Channel::CH2).await.unwrap(),
+            Setpoint::new(2.0, 0.2)
+        );
+        assert_eq!(k.inner.clears, 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_query_is_settled() {
+        let mut k = supply().await;
+        k.inner.delay = Duration::from_millis(20);
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(5), k.get_setpoint(Channel::CH1));
+        assert!(cancelled.await.is_err());
+
+        assert_eq!(
+            k.get_setpoint(Channel::CH2).await.unwrap(),
+            Setpoint::new(2.0, 0.2)
+        );
+        assert_eq!(k.inner.clears, 0);
+    }
+
+    #[tokio::test]
+    async fn lost_response_falls_back_to_clear() {
+        let mut k = supply().await;
+        k.inner.stalled = true;
+        assert!(matches!(
+            k.get_setpoint(Channel::CH1).await,
+            Err(Error::Timeout())
+        ));
+
+        // The owed response never turns up, so the next query resyncs with clear().
+        assert_eq!(
+            k.get_setpoint(Channel::CH2).await.unwrap(),
+            Setpoint::new(2.0, 0.2)
+        );
+        assert_eq!(k.inner.clears, 1);
+        assert_eq!(
+            k.get_setpoint(Channel::CH1).await.unwrap(),
+            Setpoint::new(1.0, 0.1)
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_blocking_read_keeps_its_line() {
+        let mut t = BlockingTransport::new(SlowBlocking {
+            sim: Simulator::new(Model::K2230_30_1),
+            delay: Duration::from_millis(50),
+        });
+        t.write("APPL CH1, 1.5, 0.5").await.unwrap();
+        t.write("APPL? CH1").await.unwrap();
+        let dropped = tokio::time::timeout(Duration::from_millis(5), t.read());
+        assert!(dropped.await.is_err());
+
+        // The abandoned read finishes in the background and hands its line on.
+        assert_eq!(t.read().await.unwrap(), "1.500, 0.500");
+        t.write("*IDN?").await.unwrap();
+        assert!(t.read().await.unwrap().starts_with("Keithley"));
+
+        let mut k = AsyncKeithley2230::with_transport(t).await.unwrap();
+        k.set_channel(Channel::CH3, 3.3, 1.0).await.unwrap();
+        assert_eq!(
+            k.get_setpoint(Channel::CH3).await.unwrap(),
+            Setpoint::new(3.3, 1.0)
+        );
+    }
+}
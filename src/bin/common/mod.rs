@@ -0,0 +1,76 @@
+//! Connection options shared by the command-line tools.
+
+use clap::Args;
+use keithley_2230_series::*;
+#[cfg(feature = "visa")]
+use visa_api::DefaultRM;
+
+pub type Supply = Keithley2230<Box<dyn Transport>>;
+
+/// Picks the unit; without any option the first 2230-series VISA resource is used.
+#[derive(Args)]
+#[group(multiple = false)]
+pub struct Connection {
+    /// VISA resource string, e.g. USB0::0x05E6::0x2230::9030101::INSTR.
+    #[cfg(feature = "visa")]
+    #[arg(long, global = true)]
+    pub resource: Option<String>,
+
+    /// Serial number reported by *IDN?.
+    #[cfg(feature = "visa")]
+    #[arg(long, global = true)]
+    pub serial: Option<String>,
+
+    /// Raw SCPI socket, HOST or HOST:PORT.
+    #[arg(long, global = true)]
+    pub tcp: Option<String>,
+
+    /// USB virtual COM / RS-232 device, e.g. /dev/ttyACM0.
+    #[arg(long, global = true)]
+    pub port: Option<String>,
+
+    /// In-process simulator of the given model, e.g. 2230-30-1.
+    #[arg(long, global = true)]
+    pub sim: Option<Model>,
+}
+
+impl Connection {
+    pub fn open(&self) -> Result<Supply> {
+        let transport: Box<dyn Transport> = if let Some(model) = self.sim {
+            Box::new(Simulator::new(model))
+        } else if let Some(addr) = &self.tcp {
+            Box::new(TcpTransport::connect_host(
+                addr,
+                TcpTransport::DEFAULT_TIMEOUT,
+            )?)
+        } else if let Some(path) = &self.port {
+            Box::new(SerialTransport::open(path, SerialConfig::default())?)
+        } else {
+            self.open_visa()?
+        };
+        Keithley2230::with_transport(transport)
+    }
+
+    #[cfg(not(feature = "visa"))]
+    fn open_visa(&self) -> Result<Box<dyn Transport>> {
+        Err(Error::NoInstrumentFound())
+    }
+
+    #[cfg(feature = "visa")]
+    fn open_visa(&self) -> Result<Box<dyn Transport>> {
+        let session = if let Some(resource) = &self.resource {
+            VisaSession::open(resource)?
+        } else {
+            let rm = DefaultRM::new().map_err(visa_api::Error::from)?;
+            let unit = match &self.serial {
+                Some(serial) => Keithley2230::find_unit(&rm, serial)?,
+                None => Keithley2230::list_units(&rm)?
+                    .into_iter()
+                    .next()
+                    .ok_or(Error::NoInstrumentFound())?,
+            };
+            VisaSession::open(&unit.resource)?
+        };
+        Ok(Box::new(session))
+    }
+}
@@ -0,0 +1,349 @@
+//! `k2230-tui`: live dashboard for a 2230-series supply.
+
+mod common;
+
+use clap::Parser;
+use common::{Connection, Supply};
+use keithley_2230_series::*;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(
+    name = "k2230-tui",
+    version,
+    about = "Live dashboard for a Keithley 2230-series power supply"
+)]
+struct Cli {
+    #[command(flatten)]
+    conn: Connection,
+
+    /// Milliseconds between instrument polls.
+    #[arg(long, default_value_t = 500)]
+    refresh: u64,
+}
+
+const STEPS: [f32; 4] = [0.001, 0.01, 0.1, 1.0];
+
+/// Within this fraction of the current limit a channel is taken to be in CC.
+const CC_MARGIN: f32 = 0.99;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Voltage,
+    Current,
+}
+
+/// Everything shown on screen, read in one poll.
+struct Snapshot {
+    setpoints: Vec<Setpoint>,
+    enabled: Vec<State>,
+    meas: Meas,
+    output: State,
+    parallel: State,
+    series: State,
+}
+
+struct App {
+    k: Supply,
+    channels: Vec<Channel>,
+    selected: usize,
+    field: Field,
+    step: usize,
+    snapshot: Option<Snapshot>,
+    status: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // Start from an empty error queue so the status line only shows our own errors.
+    let k = match cli
+        .conn
+        .open()
+        .and_then(|mut k| k.read_error_queue().map(|_| k))
+    {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("k2230-tui: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut app = App::new(k);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal, Duration::from_millis(cli.refresh));
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("k2230-tui: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+impl App {
+    fn new(mut k: Supply) -> Self {
+        k.set_error_checking(true);
+        let channels = k.model_info().channels.clone();
+        Self {
+            k,
+            channels,
+            selected: 0,
+            field: Field::Voltage,
+            step: 2,
+            snapshot: None,
+            status: None,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, refresh: Duration) -> Result<()> {
+        let mut next_poll = Instant::now();
+        loop {
+            if Instant::now() >= next_poll {
+                self.poll();
+                next_poll = Instant::now() + refresh;
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let wait = next_poll.saturating_duration_since(Instant::now());
+            if !event::poll(wait)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let result = match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.selected = self.selected.saturating_sub(1);
+                    Ok(())
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.selected = (self.selected + 1).min(self.channels.len() - 1);
+                    Ok(())
+                }
+                KeyCode::Tab => {
+                    self.field = match self.field {
+                        Field::Voltage => Field::Current,
+                        Field::Current => Field::Voltage,
+                    };
+                    Ok(())
+                }
+                KeyCode::Char('[') => {
+                    self.step = self.step.saturating_sub(1);
+                    Ok(())
+                }
+                KeyCode::Char(']') => {
+                    self.step = (self.step + 1).min(STEPS.len() - 1);
+                    Ok(())
+                }
+                KeyCode::Right | KeyCode::Char('+') | KeyCode::Char('=') => self.adjust(1.0),
+                KeyCode::Left | KeyCode::Char('-') => self.adjust(-1.0),
+                KeyCode::Char(' ') | KeyCode::Enter => self.toggle_channel(),
+                KeyCode::Char('o') => self.toggle(|s| s.output, Supply::enable_output),
+                KeyCode::Char('p') => self.toggle(|s| s.parallel, Supply::set_paralel),
+                KeyCode::Char('s') => self.toggle(|s| s.series, Supply::set_series),
+                _ => continue,
+            };
+            match result {
+                Ok(()) => self.status = None,
+                Err(e) => self.status = Some(e.to_string()),
+            }
+            // Show the effect of a key press right away.
+            next_poll = Instant::now();
+        }
+    }
+
+    fn poll(&mut self) {
+        match self.read_snapshot() {
+            Ok(snapshot) => self.snapshot = Some(snapshot),
+            Err(e) => self.status = Some(e.to_string()),
+        }
+    }
+
+    fn read_snapshot(&mut self) -> Result<Snapshot> {
+        let mut setpoints = Vec::new();
+        let mut enabled = Vec::new();
+        for &ch in &self.channels {
+            setpoints.push(self.k.get_setpoint(ch)?);
+            enabled.push(self.k.channel_state(ch)?);
+        }
+        Ok(Snapshot {
+            setpoints,
+            enabled,
+            meas: self.k.read_all()?,
+            output: self.k.output_state()?,
+            parallel: self.k.parallel_state()?,
+            series: self.k.series_state()?,
+        })
+    }
+
+    fn adjust(&mut self, direction: f32) -> Result<()> {
+        let Some(snapshot) = &self.snapshot else {
+            return Ok(());
+        };
+        let ch = self.channels[self.selected];
+        let Some(&limits) = self.k.model_info().channel_limits(ch) else {
+            return Err(Error::UnsupportedChannel(ch));
+        };
+        let Setpoint {
+            mut voltage,
+            mut current,
+        } = snapshot.setpoints[self.selected];
+        let delta = direction * STEPS[self.step];
+        match self.field {
+            Field::Voltage => voltage = (voltage + delta).clamp(0.0, limits.max_voltage),
+            Field::Current => current = (current + delta).clamp(0.0, limits.max_current),
+        }
+        self.k.set_channel(ch, voltage, current)
+    }
+
+    fn toggle_channel(&mut self) -> Result<()> {
+        let Some(snapshot) = &self.snapshot else {
+            return Ok(());
+        };
+        let state = flip(snapshot.enabled[self.selected]);
+        self.k.enable_channel(self.channels[self.selected], state)
+    }
+
+    fn toggle(
+        &mut self,
+        current: impl Fn(&Snapshot) -> State,
+        set: impl Fn(&mut Supply, State) -> Result<()>,
+    ) -> Result<()> {
+        let Some(snapshot) = &self.snapshot else {
+            return Ok(());
+        };
+        let state = flip(current(snapshot));
+        set(&mut self.k, state)
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, table, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(self.channels.len() as u16 + 3),
+            Constraint::Length(4),
+        ])
+        .areas(frame.area());
+
+        let info = self.k.model_info();
+        let title = format!(" Keithley {}  SN {} ", info.model, info.serial);
+        let summary = match &self.snapshot {
+            Some(s) => Line::from(vec![
+                "Output ".into(),
+                state_span(s.output),
+                format!("   Combine {}", combine_mode(s)).into(),
+                format!("   Step {} {}", STEPS[self.step], self.field_unit()).into(),
+            ]),
+            None => Line::from("Waiting for first reading..."),
+        };
+        frame.render_widget(
+            Paragraph::new(summary).block(Block::bordered().title(title)),
+            header,
+        );
+
+        let rows = self.channels.iter().enumerate().map(|(n, ch)| {
+            let Some(s) = &self.snapshot else {
+                return Row::new(vec![Cell::from(ch.to_string())]);
+            };
+            let set = s.setpoints[n];
+            let m = s.meas.channel(*ch);
+            let editing = |field: Field| {
+                if n == self.selected && self.field == field {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new()
+                }
+            };
+            Row::new(vec![
+                Cell::from(ch.to_string()),
+                Cell::from(format!("{:.3}", set.voltage)).style(editing(Field::Voltage)),
+                Cell::from(format!("{:.3}", set.current)).style(editing(Field::Current)),
+                Cell::from(format!("{:.3}", m.v)),
+                Cell::from(format!("{:.3}", m.i)),
+                Cell::from(format!("{:.3}", m.p)),
+                Cell::from(state_span(s.enabled[n])),
+                Cell::from(regulation(set, m, s.enabled[n], s.output)),
+            ])
+            .style(if n == self.selected {
+                Style::new().add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            })
+        });
+        let widths = [Constraint::Length(5)]
+            .into_iter()
+            .chain([Constraint::Length(9); 7]);
+        let table_widget = Table::new(rows, widths)
+            .header(
+                Row::new(["", "Set V", "Set A", "V", "A", "W", "Out", "Mode"])
+                    .style(Style::new().fg(Color::Cyan)),
+            )
+            .block(Block::bordered().title(" Channels "));
+        frame.render_widget(table_widget, table);
+
+        let help = Line::from(
+            "↑↓ channel  Tab V/A  ←→ adjust  [ ] step  Space on/off  \
+             o output  p parallel  s series  q quit",
+        );
+        let status = match &self.status {
+            Some(message) => Line::from(message.as_str()).red(),
+            None => Line::from(""),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![help, status]).block(Block::bordered()),
+            footer,
+        );
+    }
+
+    fn field_unit(&self) -> &'static str {
+        match self.field {
+            Field::Voltage => "V",
+            Field::Current => "A",
+        }
+    }
+}
+
+fn flip(state: State) -> State {
+    match state {
+        State::ON => State::OFF,
+        State::OFF => State::ON,
+    }
+}
+
+fn state_span(state: State) -> ratatui::text::Span<'static> {
+    match state {
+        State::ON => "ON".green(),
+        State::OFF => "OFF".dark_gray(),
+    }
+}
+
+fn combine_mode(s: &Snapshot) -> &'static str {
+    match (s.parallel, s.series) {
+        (State::ON, _) => "parallel",
+        (_, State::ON) => "series",
+        _ => "independent",
+    }
+}
+
+/// The supply doesn't report CV/CC, so infer it: a live channel sitting at
+/// its current limit with the voltage below setpoint is current limited.
+fn regulation(set: Setpoint, m: &ChMeas, enabled: State, output: State) -> &'static str {
+    if enabled == State::OFF || output == State::OFF {
+        return "-";
+    }
+    if m.i >= set.current * CC_MARGIN && m.v < set.voltage * CC_MARGIN {
+        "CC"
+    } else {
+        "CV"
+    }
+}
@@ -0,0 +1,219 @@
+//! `k2230`: drive a 2230-series supply from the shell.
+//!
+//! Exit codes: 0 on success, 2 on usage errors, 3 when the instrument reports
+//! a SCPI error, 4 when no matching unit could be opened and 1 otherwise.
+
+mod common;
+
+use clap::{Parser, Subcommand};
+use common::{Connection, Supply};
+use keithley_2230_series::*;
+use serde_json::{json, Map, Value};
+use std::io::{ErrorKind, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "visa")]
+use visa_api::DefaultRM;
+
+#[derive(Parser)]
+#[command(
+    name = "k2230",
+    version,
+    about = "Control a Keithley 2230-series power supply"
+)]
+struct Cli {
+    #[command(flatten)]
+    conn: Connection,
+
+    /// Print machine-readable JSON instead of tab separated text.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List connected 2230-series units.
+    #[cfg(feature = "visa")]
+    List,
+    /// Print model, serial number and firmware.
+    Idn,
+    /// Set a channel's voltage and current setpoints.
+    Set {
+        channel: Channel,
+        voltage: f32,
+        current: f32,
+    },
+    /// Enable a channel, or the main output if no channel is given.
+    On { channel: Option<Channel> },
+    /// Disable a channel, or the main output if no channel is given.
+    Off { channel: Option<Channel> },
+    /// Read voltage, current and power of every channel.
+    Measure,
+    /// Stream measurements to stdout as CSV (or JSON lines with --json).
+    Log {
+        /// Seconds between samples.
+        #[arg(long, default_value_t = 1.0)]
+        interval: f64,
+        /// Stop after this many samples.
+        #[arg(long)]
+        count: Option<u64>,
+    },
+    /// Save the present setup to a memory slot.
+    Save { slot: u8 },
+    /// Recall a setup from a memory slot.
+    Recall { slot: u8 },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Error::Io(e)) if e.kind() == ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("k2230: {}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn exit_code(e: &Error) -> u8 {
+    match e {
+        Error::Instrument(_) => 3,
+        Error::NoInstrumentFound() | Error::UnitNotFound(_) | Error::AmbiguousUnit { .. } => 4,
+        _ => 1,
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    #[cfg(feature = "visa")]
+    if let Command::List = cli.command {
+        let rm = DefaultRM::new().map_err(visa_api::Error::from)?;
+        let units = Keithley2230::list_units(&rm)?;
+        if cli.json {
+            return print_json(&units);
+        }
+        for unit in units {
+            let info = &unit.info;
+            println!("{}\t{}\t{}", unit.resource, info.model, info.serial);
+        }
+        return Ok(());
+    }
+
+    let mut k = cli.conn.open()?;
+    // Start from an empty queue so only errors caused by this run are reported.
+    k.read_error_queue()?;
+    k.set_error_checking(true);
+    match cli.command {
+        #[cfg(feature = "visa")]
+        Command::List => unreachable!(),
+        Command::Idn => {
+            let info = k.model_info();
+            if cli.json {
+                print_json(info)?;
+            } else {
+                println!("{}\t{}\t{}", info.model, info.serial, info.firmware);
+            }
+        }
+        Command::Set {
+            channel,
+            voltage,
+            current,
+        } => k.set_channel(channel, voltage, current)?,
+        Command::On { channel } => switch(&mut k, channel, State::ON)?,
+        Command::Off { channel } => switch(&mut k, channel, State::OFF)?,
+        Command::Measure => {
+            let meas = k.read_all()?;
+            check_errors(&mut k)?;
+            let channels = k.model_info().channels.clone();
+            if cli.json {
+                return print_json(&measurement_json(&meas, &channels));
+            }
+            for ch in channels {
+                let m = meas.channel(ch);
+                println!("{}\t{:.3}\t{:.3}\t{:.3}", ch, m.v, m.i, m.p);
+            }
+        }
+        Command::Log { interval, count } => log(&mut k, interval, count, cli.json)?,
+        Command::Save { slot } => k.save_state(MemorySlot::new(slot)?)?,
+        Command::Recall { slot } => k.recall_state(MemorySlot::new(slot)?)?,
+    }
+    // Final sweep for anything the queries above left in the queue.
+    check_errors(&mut k)
+}
+
+fn check_errors(k: &mut Supply) -> Result<()> {
+    match k.read_error_queue()?.into_iter().next() {
+        Some(error) => Err(Error::Instrument(error)),
+        None => Ok(()),
+    }
+}
+
+fn switch(k: &mut Supply, channel: Option<Channel>, state: State) -> Result<()> {
+    match channel {
+        Some(ch) => k.enable_channel(ch, state),
+        None => k.enable_output(state),
+    }
+}
+
+fn log(k: &mut Supply, interval: f64, count: Option<u64>, json: bool) -> Result<()> {
+    if !interval.is_finite() || interval <= 0.0 {
+        return Err(Error::OutOfRange {
+            parameter: "interval (s)".to_string(),
+            value: interval as f32,
+            min: 0.0,
+            max: f32::MAX,
+        });
+    }
+    let interval = Duration::from_secs_f64(interval);
+    let channels = k.model_info().channels.clone();
+    let mut out = std::io::stdout().lock();
+    if !json {
+        writeln!(out, "{}", csv_header(&channels))?;
+    }
+
+    let start = Instant::now();
+    let mut next = start;
+    let mut taken = 0;
+    while count.is_none_or(|n| taken < n) {
+        let meas = k.read_all()?;
+        check_errors(k)?;
+        let now = SystemTime::now();
+        let elapsed = start.elapsed();
+        if json {
+            let unix_time = now
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            let mut row = measurement_json(&meas, &channels);
+            row.insert("unix_time".to_string(), json!(unix_time));
+            row.insert("elapsed_s".to_string(), json!(elapsed.as_secs_f64()));
+            writeln!(out, "{}", Value::Object(row))?;
+        } else {
+            writeln!(out, "{}", csv_row(&channels, now, elapsed, &meas))?;
+        }
+        out.flush()?;
+        taken += 1;
+
+        next += interval;
+        if let Some(wait) = next.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        }
+    }
+    Ok(())
+}
+
+fn measurement_json(meas: &Meas, channels: &[Channel]) -> Map<String, Value> {
+    channels
+        .iter()
+        .map(|ch| (ch.as_ref().to_lowercase(), json!(meas.channel(*ch))))
+        .collect()
+}
+
+fn print_json<S: serde::Serialize + ?Sized>(value: &S) -> Result<()> {
+    let text = serde_json::to_string(value).map_err(std::io::Error::from)?;
+    println!("{}", text);
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use crate::{Channel, Error, Keithley2230, Result, Setpoint, State, Transport};
+
+/// Programmed state of one channel as captured by [`Keithley2230::snapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelConfig {
+    pub channel: Channel,
+    pub setpoint: Setpoint,
+    pub output: State,
+    pub ovp_level: f32,
+    pub ovp: State,
+}
+
+/// Everything [`Keithley2230::apply`] needs to put the supply back into a known state.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SupplyConfig {
+    pub channels: Vec<ChannelConfig>,
+    pub output: State,
+    pub parallel: State,
+    pub series: State,
+    pub tracking: State,
+}
+
+impl SupplyConfig {
+    pub fn channel(&self, ch: Channel) -> Option<&ChannelConfig> {
+        self.channels.iter().find(|c| c.channel == ch)
+    }
+}
+
+impl<T: Transport> Keithley2230<T> {
+    /// Reads back the full programmed state of every channel and the coupling modes.
+    pub fn snapshot(&mut self) -> Result<SupplyConfig> {
+        let mut channels = Vec::new();
+        for ch in self.info.channels.clone() {
+            channels.push(ChannelConfig {
+                channel: ch,
+                setpoint: self.get_setpoint(ch)?,
+                output: self.channel_state(ch)?,
+                ovp_level: self.get_ovp(ch)?,
+                ovp: self.ovp_state(ch)?,
+            });
+        }
+        Ok(SupplyConfig {
+            channels,
+            output: self.output_state()?,
+            parallel: self.parallel_state()?,
+            series: self.series_state()?,
+            tracking: self.tracking_state()?,
+        })
+    }
+
+    /// Reprograms the supply to `config`.
+    ///
+    /// The whole config is checked before anything is sent, so a rejected config
+    /// leaves the supply untouched. Outputs are then switched off and only
+    /// re-enabled once every channel's protection and setpoints are in place.
+    pub fn apply(&mut self, config: &SupplyConfig) -> Result<()> {
+        for (n, c) in config.channels.iter().enumerate() {
+            if config.channels[..n].iter().any(|p| p.channel == c.channel) {
+                return Err(Error::DuplicateChannel(c.channel));
+            }
+            self.validate_setpoint(c.channel, c.setpoint.voltage, c.setpoint.current)?;
+            self.validate_ovp(c.channel, c.ovp_level)?;
+        }
+
+        self.enable_output(State::OFF)?;
+        for ch in self.info.channels.clone() {
+            self.enable_channel(ch, State::OFF)?;
+        }
+
+        // Parallel and series are mutually exclusive, so drop both before setting either.
+        self.set_paralel(State::OFF)?;
+        self.set_series(State::OFF)?;
+        if config.parallel == State::ON {
+            self.set_paralel(State::ON)?;
+        }
+        if config.series == State::ON {
+            self.set_series(State::ON)?;
+        }
+        self.set_tracking(config.tracking)?;
+
+        for c in &config.channels {
+            self.set_ovp(c.channel, c.ovp_level)?;
+            self.enable_ovp(c.channel, c.ovp)?;
+            self.set_channel(c.channel, c.setpoint.voltage, c.setpoint.current)?;
+        }
+        for c in &config.channels {
+            self.enable_channel(c.channel, c.output)?;
+        }
+        self.enable_output(config.output)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    fn configured() -> Keithley2230<crate::Simulator> {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.set_channel(Channel::CH1, 5.0, 1.0).unwrap();
+        k.set_channel(Channel::CH3, 3.3, 2.0).unwrap();
+        k.set_ovp(Channel::CH1, 6.0).unwrap();
+        k.enable_ovp(Channel::CH1, State::ON).unwrap();
+        k.enable_channel(Channel::CH1, State::ON).unwrap();
+        k.enable_channel(Channel::CH3, State::ON).unwrap();
+        k.enable_output(State::ON).unwrap();
+        k
+    }
+
+    #[test]
+    fn snapshot_apply_round_trip() {
+        let mut k = configured();
+        let saved = k.snapshot().unwrap();
+        assert_eq!(saved.channels.len(), 3);
+        assert_eq!(saved.output, State::ON);
+        assert_eq!(
+            saved.channel(Channel::CH1).unwrap().setpoint,
+            Setpoint::new(5.0, 1.0)
+        );
+
+        let mut other = Keithley2230::simulated(Model::K2230_30_1);
+        other.set_error_checking(true);
+        other.set_channel(Channel::CH2, 12.0, 0.1).unwrap();
+        other.enable_channel(Channel::CH2, State::ON).unwrap();
+        other.apply(&saved).unwrap();
+        assert_eq!(other.snapshot().unwrap(), saved);
+    }
+
+    #[test]
+    fn rejected_config_leaves_supply_untouched() {
+        let mut k = configured();
+        let before = k.snapshot().unwrap();
+
+        let mut config = before.clone();
+        config.channels[0].ovp_level = 100.0;
+        assert!(matches!(
+            k.apply(&config),
+            Err(Error::OutOfRange { parameter, .. }) if parameter == "CH1 OVP level"
+        ));
+
+        let mut config = before.clone();
+        config.channels[2].setpoint.voltage = 7.0;
+        assert!(matches!(k.apply(&config), Err(Error::OutOfRange { .. })));
+
+        let mut config = before.clone();
+        config.channels.push(config.channels[0].clone());
+        assert!(matches!(
+            k.apply(&config),
+            Err(Error::DuplicateChannel(Channel::CH1))
+        ));
+
+        assert_eq!(k.snapshot().unwrap(), before);
+    }
+}
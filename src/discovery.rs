@@ -0,0 +1,128 @@
+use crate::{Error, Keithley2230, ModelInfo, Reconnect, Result, Transport};
+use std::ffi::CString;
+use visa_api::{DefaultRM, Instrument, Visa};
+use visa_rs::AsResourceManager;
+
+/// A connected 2230-series unit found by [`Keithley2230::list_units`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitInfo {
+    /// VISA resource string, e.g. `USB0::0x05E6::0x2230::9030101::INSTR`.
+    pub resource: String,
+    pub info: ModelInfo,
+}
+
+impl Keithley2230 {
+    /// Identifies every VISA resource and returns the ones that are supported
+    /// 2230-series units. Resources that can't be opened or don't answer
+    /// `*IDN?` are skipped.
+    pub fn list_units(rm: &DefaultRM) -> Result<Vec<UnitInfo>> {
+        let resources = match Instrument::read_resources(rm) {
+            Ok(resources) => resources,
+            // viFindRsrc reports an empty bus as an error.
+            Err(visa_api::Error::VisaRs(visa_rs::Error(
+                visa_rs::enums::status::ErrorCode::ErrorRsrcNfound,
+            ))) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut units = Vec::new();
+        for resource in resources {
+            let resource = CString::from(resource).to_string_lossy().into_owned();
+            let Ok(mut session) = open_session(rm, &resource) else {
+                continue;
+            };
+            let Ok(idn) = session.query(visa_api::Commands::Identify.as_ref()) else {
+                continue;
+            };
+            if let Ok(info) = ModelInfo::from_idn(&idn) {
+                units.push(UnitInfo { resource, info });
+            }
+        }
+        Ok(units)
+    }
+
+    /// Opens a specific VISA resource, e.g. `TCPIP0::192.168.0.10::5025::SOCKET`.
+    pub fn open_resource(rm: &DefaultRM, resource: &str) -> Result<Self> {
+        Self::with_transport(open_session(rm, resource)?)
+    }
+
+    /// Finds the one unit whose `*IDN?` serial number is `serial`.
+    pub fn find_unit(rm: &DefaultRM, serial: &str) -> Result<UnitInfo> {
+        let mut matches = Self::list_units(rm)?
+            .into_iter()
+            .filter(|u| u.info.serial == serial.trim())
+            .collect::<Vec<UnitInfo>>();
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(Error::UnitNotFound(serial.to_string())),
+            _ => Err(Error::AmbiguousUnit {
+                serial: serial.to_string(),
+                resources: matches.into_iter().map(|u| u.resource).collect(),
+            }),
+        }
+    }
+
+    /// Opens the unit whose `*IDN?` serial number is `serial`.
+    pub fn open_by_serial(rm: &DefaultRM, serial: &str) -> Result<Self> {
+        let unit = Self::find_unit(rm, serial)?;
+        Self::open_resource(rm, &unit.resource)
+    }
+}
+
+/// A VISA session that remembers its resource string so it can be re-opened
+/// after the link drops, e.g. inside a [`crate::ResilientTransport`].
+#[derive(Debug)]
+pub struct VisaSession {
+    rm: DefaultRM,
+    resource: String,
+    session: Instrument,
+}
+
+impl VisaSession {
+    pub fn open(resource: &str) -> Result<Self> {
+        let rm = DefaultRM::new().map_err(visa_api::Error::from)?;
+        let session = open_session(&rm, resource)?;
+        Ok(Self {
+            rm,
+            resource: resource.to_string(),
+            session,
+        })
+    }
+
+    pub fn resource(&self) -> &str {
+        &self.resource
+    }
+}
+
+impl Transport for VisaSession {
+    fn write(&mut self, command: &str) -> Result<()> {
+        Transport::write(&mut self.session, command)
+    }
+
+    fn read(&mut self) -> Result<String> {
+        Transport::read(&mut self.session)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        Transport::clear(&mut self.session)
+    }
+}
+
+impl Reconnect for VisaSession {
+    fn reconnect(&mut self) -> Result<()> {
+        self.session = open_session(&self.rm, &self.resource)?;
+        Ok(())
+    }
+}
+
+fn open_session(rm: &DefaultRM, resource: &str) -> Result<Instrument> {
+    let name = CString::new(resource).map_err(|_| Error::UnitNotFound(resource.to_string()))?;
+    let session = rm
+        .open(
+            &name.into(),
+            visa_rs::flags::AccessMode::NO_LOCK,
+            visa_rs::TIMEOUT_IMMEDIATE,
+        )
+        .map_err(visa_api::Error::from)?;
+    Ok(session)
+}
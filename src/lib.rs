@@ -1,25 +1,246 @@
 use std::str::FromStr;
-use visa_api::*;
-pub struct Keithley2230 {
-    pub inner: visa_api::Instrument,
+#[cfg(feature = "visa")]
+use visa_api::{DefaultRM, Instrument};
+
+#[cfg(feature = "async")]
+mod async_api;
+mod config;
+#[cfg(feature = "visa")]
+mod discovery;
+mod list;
+mod logger;
+mod memory;
+mod model;
+#[cfg(feature = "profile")]
+mod profile;
+mod protection;
+mod ramp;
+mod retry;
+mod sequence;
+#[cfg(feature = "serial")]
+mod serial;
+mod sim;
+mod tcp;
+mod transport;
+
+#[cfg(feature = "async")]
+pub use async_api::{
+    AsyncKeithley2230, AsyncSimulator, AsyncTcpTransport, AsyncTransport, BlockingTransport,
+};
+pub use config::{ChannelConfig, SupplyConfig};
+#[cfg(feature = "visa")]
+pub use discovery::{UnitInfo, VisaSession};
+pub use list::{ListSequence, ListSlot, ListStep};
+pub use logger::{csv_header, csv_row, DataLogger, LoggerConfig, Rotation};
+pub use memory::{MemorySlot, SlotEntry, SlotRegistry};
+pub use model::{ChannelLimits, Features, Model, ModelInfo};
+#[cfg(feature = "profile")]
+pub use profile::{MeasurementPoint, MeasurementResult, Profile, ProfileChannel, ProfileResult};
+pub use ramp::{CancelToken, Ramp};
+pub use retry::{is_idempotent, Reconnect, ResilientTransport, RetryPolicy};
+pub use sequence::{PowerSequence, PowerStep, VoltageGate};
+#[cfg(feature = "serial")]
+pub use serial::{DataBits, FlowControl, Parity, SerialConfig, SerialTransport, StopBits};
+pub use sim::Simulator;
+pub use tcp::TcpTransport;
+pub use transport::Transport;
+
+#[cfg(feature = "visa")]
+pub struct Keithley2230<T: Transport = Instrument> {
+    pub inner: T,
+    info: ModelInfo,
+    check_errors: bool,
+}
+
+#[cfg(not(feature = "visa"))]
+pub struct Keithley2230<T: Transport> {
+    pub inner: T,
+    info: ModelInfo,
+    check_errors: bool,
 }
 
 pub const MANUFACTURER: &str = "Keithley Instruments";
+pub(crate) const IDENTIFY: &str = "*IDN?";
 pub const MODEL: &str = "2230";
 
-#[derive(Clone, Copy, Debug, thiserror::Error)]
+pub const OUTPUT_TIMER_MIN: std::time::Duration = std::time::Duration::from_millis(100);
+pub const OUTPUT_TIMER_MAX: std::time::Duration = std::time::Duration::from_millis(99_999_900);
+
+/// Upper bound on `SYST:ERR?` reads while draining, in case the queue never reports empty.
+const ERROR_QUEUE_DEPTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[cfg(feature = "visa")]
     #[error(transparent)]
     VisaApiError(#[from] visa_api::Error),
     #[error(transparent)]
     StrumParseError(#[from] strum::ParseError),
     #[error("No Instrument found")]
     NoInstrumentFound(),
+    #[error("Unsupported model: {0}")]
+    UnsupportedModel(String),
+    #[error("{0} is not available on this model")]
+    UnsupportedChannel(Channel),
+    #[error("{0} is configured more than once")]
+    DuplicateChannel(Channel),
+    #[error("{0} is not supported by this model")]
+    UnsupportedFeature(&'static str),
+    #[error("No unit matching {0:?} found")]
+    UnitNotFound(String),
+    #[error("Serial number {serial:?} matches several units: {resources:?}")]
+    AmbiguousUnit {
+        serial: String,
+        resources: Vec<String>,
+    },
+    #[error("No memory slot named {0:?}")]
+    UnknownSlotName(String),
+    #[error("Cancelled")]
+    Cancelled(),
+    #[error("Logger thread panicked: {0}")]
+    LoggerPanicked(String),
+    #[error("{channel} drew {measured} A, above the {limit} A abort threshold")]
+    CurrentLimitExceeded {
+        channel: Channel,
+        measured: f32,
+        limit: f32,
+    },
+    #[error("{channel} did not settle at {target} V (last read {measured} V)")]
+    GateTimeout {
+        channel: Channel,
+        target: f32,
+        measured: f32,
+    },
+    #[cfg(feature = "profile")]
+    #[error("Invalid profile: {0}")]
+    Profile(String),
+    #[error("{parameter} = {value} is outside [{min}, {max}]")]
+    OutOfRange {
+        parameter: String,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    #[error("Timed out waiting for a response")]
+    Timeout(),
+    #[error("Connection was re-established but {0:?} was not replayed")]
+    NotReplayed(String),
+    #[error("Instrument reported error {0}")]
+    Instrument(ScpiError),
+    #[error("Malformed response to {command}: {raw:?}")]
+    ParseResponse { command: String, raw: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "serial")]
+    #[error(transparent)]
+    Serial(#[from] serialport::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, PartialEq, strum::AsRefStr, strum::Display, Default, strum::EnumString)]
+impl Error {
+    pub(crate) fn parse_response(command: &str, raw: &str) -> Self {
+        Self::ParseResponse {
+            command: command.to_string(),
+            raw: raw.to_string(),
+        }
+    }
+
+    /// Whether the error comes from a dropped or stalled link rather than from
+    /// the instrument itself, so re-opening the session may help.
+    pub fn is_transient(&self) -> bool {
+        use std::io::ErrorKind;
+        let io_transient = |e: &std::io::Error| {
+            matches!(
+                e.kind(),
+                ErrorKind::BrokenPipe
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::TimedOut
+                    | ErrorKind::WouldBlock
+            )
+        };
+        match self {
+            Error::Timeout() => true,
+            Error::Io(e) => io_transient(e),
+            #[cfg(feature = "visa")]
+            Error::VisaApiError(visa_api::Error::VisaRs(visa_rs::Error(code))) => {
+                use visa_rs::enums::status::ErrorCode;
+                matches!(
+                    code,
+                    ErrorCode::ErrorConnLost
+                        | ErrorCode::ErrorIo
+                        | ErrorCode::ErrorTmo
+                        | ErrorCode::ErrorInvObject
+                )
+            }
+            #[cfg(feature = "serial")]
+            Error::Serial(e) => matches!(
+                e.kind(),
+                serialport::ErrorKind::NoDevice | serialport::ErrorKind::Io(_)
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Fails with [`Error::OutOfRange`] unless `min <= value <= max`; NaN is always rejected.
+pub(crate) fn check_range(parameter: &str, value: f32, min: f32, max: f32) -> Result<()> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(Error::OutOfRange {
+            parameter: parameter.to_string(),
+            value,
+            min,
+            max,
+        })
+    }
+}
+
+/// An entry of the instrument's `SYST:ERR?` queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScpiError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl std::fmt::Display for ScpiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, \"{}\"", self.code, self.message)
+    }
+}
+
+impl FromStr for ScpiError {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (code, message) = s.split_once(',').unwrap_or((s, ""));
+        let code = code
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| Error::parse_response("SYST:ERR?", s))?;
+        let message = message.trim().trim_matches('"').to_string();
+        Ok(Self { code, message })
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::AsRefStr,
+    strum::Display,
+    Default,
+    strum::EnumString,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Channel {
     #[default]
     #[strum(serialize = "CH1")]
@@ -30,7 +251,10 @@ pub enum Channel {
     CH3,
 }
 
-#[derive(Debug, PartialEq, strum::AsRefStr, strum::Display, Default)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, strum::AsRefStr, strum::Display, Default, strum::EnumString,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     #[default]
     #[strum(serialize = "ON", serialize = "1")]
@@ -38,126 +262,292 @@ pub enum State {
     #[strum(serialize = "OFF", serialize = "0")]
     OFF,
 }
-#[derive(Debug, Default)]
+/// Whether a reading returns the instrument's latest cached sample (`FETCh`)
+/// or triggers a fresh acquisition (`MEASure`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum::AsRefStr, strum::Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MeasureMode {
+    #[default]
+    #[strum(serialize = "FETC")]
+    Fetch,
+    #[strum(serialize = "MEAS")]
+    Measure,
+}
+
+/// Programmed voltage and current limit of one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Setpoint {
+    pub voltage: f32,
+    pub current: f32,
+}
+
+impl Setpoint {
+    pub fn new(voltage: f32, current: f32) -> Self {
+        Self { voltage, current }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Meas {
     pub ch1: ChMeas,
     pub ch2: ChMeas,
     pub ch3: ChMeas,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChMeas {
     pub v: f32,
     pub i: f32,
     pub p: f32,
 }
 
+impl Meas {
+    pub fn channel(&self, ch: Channel) -> &ChMeas {
+        match ch {
+            Channel::CH1 => &self.ch1,
+            Channel::CH2 => &self.ch2,
+            Channel::CH3 => &self.ch3,
+        }
+    }
+}
+
 impl ChMeas {
     pub fn new(v: f32, i: f32, p: f32) -> Self {
         Self { v, i, p }
     }
 }
 
+#[cfg(feature = "visa")]
 impl Keithley2230 {
+    /// Opens the first supported unit found by [`list_units`](Self::list_units).
     pub fn new(rm: &DefaultRM) -> Result<Self> {
-        let session = Instrument::new_session(&rm, MANUFACTURER, MODEL)?;
-        if let Some(session) = session {
-            Ok(Self { inner: session })
-        } else {
-            Err(Error::NoInstrumentFound())
+        let unit = Self::list_units(rm)?
+            .into_iter()
+            .next()
+            .ok_or(Error::NoInstrumentFound())?;
+        Self::open_resource(rm, &unit.resource)
+    }
+}
+
+impl Keithley2230<TcpTransport> {
+    /// Connects to `addr` (e.g. `"192.168.0.10"` or `"192.168.0.10:5025"`) over
+    /// a raw SCPI socket.
+    pub fn new_tcp(addr: &str) -> Result<Self> {
+        let transport = TcpTransport::connect_host(addr, TcpTransport::DEFAULT_TIMEOUT)?;
+        Self::with_transport(transport)
+    }
+}
+
+#[cfg(feature = "serial")]
+impl Keithley2230<SerialTransport> {
+    /// Opens the USB virtual COM / RS-232 port at `path`, e.g. `/dev/ttyACM0`.
+    pub fn new_serial(path: &str, config: SerialConfig) -> Result<Self> {
+        let transport = SerialTransport::open(path, config)?;
+        Self::with_transport(transport)
+    }
+}
+
+impl Keithley2230<Simulator> {
+    pub fn simulated(model: Model) -> Self {
+        Self::with_transport(Simulator::new(model)).expect("simulator reports a supported *IDN?")
+    }
+}
+
+impl<T: Transport> Keithley2230<T> {
+    /// Wraps an open transport, identifying the unit with `*IDN?`.
+    pub fn with_transport(mut inner: T) -> Result<Self> {
+        let idn = inner.query(IDENTIFY)?;
+        let info = ModelInfo::from_idn(&idn)?;
+        Ok(Self {
+            inner,
+            info,
+            check_errors: false,
+        })
+    }
+
+    pub fn model_info(&self) -> &ModelInfo {
+        &self.info
+    }
+
+    /// When enabled, every setter drains `SYST:ERR?` afterwards and fails with
+    /// [`Error::Instrument`] if the instrument rejected the command.
+    pub fn set_error_checking(&mut self, enabled: bool) {
+        self.check_errors = enabled;
+    }
+
+    pub fn error_checking(&self) -> bool {
+        self.check_errors
+    }
+
+    /// Drains the instrument's error queue, oldest first.
+    pub fn read_error_queue(&mut self) -> Result<Vec<ScpiError>> {
+        let mut errors = Vec::new();
+        for _ in 0..ERROR_QUEUE_DEPTH {
+            let error = ScpiError::from_str(&self.inner.query("SYST:ERR?")?)?;
+            if error.code == 0 {
+                break;
+            }
+            errors.push(error);
+        }
+        Ok(errors)
+    }
+
+    pub(crate) fn command(&mut self, cmd: &str) -> Result<()> {
+        self.inner.write(cmd)?;
+        if self.check_errors {
+            if let Some(error) = self.read_error_queue()?.into_iter().next() {
+                return Err(Error::Instrument(error));
+            }
         }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    pub(crate) fn channel_limits(&self, ch: Channel) -> Result<ChannelLimits> {
+        self.info
+            .channel_limits(ch)
+            .copied()
+            .ok_or(Error::UnsupportedChannel(ch))
+    }
+
+    /// Checks `v`/`i` against the connected model's limits for `ch` without
+    /// sending anything.
+    pub fn validate_setpoint(&self, ch: Channel, v: f32, i: f32) -> Result<()> {
+        self.info.validate_setpoint(ch, v, i)
     }
 
     pub fn set_channel(&mut self, ch: Channel, v: f32, i: f32) -> Result<()> {
+        self.validate_setpoint(ch, v, i)?;
         let cmd = format!("APPL {}, {}, {}", ch, v, i);
-        self.inner.write(&cmd)?;
+        self.command(&cmd)?;
         Ok(())
     }
 
+    pub fn get_setpoint(&mut self, ch: Channel) -> Result<Setpoint> {
+        self.channel_limits(ch)?;
+        let cmd = format!("APPL? {}", ch);
+        let response = self.inner.query(&cmd)?;
+        parse_setpoint(&cmd, &response)
+    }
+
+    pub fn get_voltage_setpoint(&mut self, ch: Channel) -> Result<f32> {
+        self.with_channel(ch, |k| k.query_number("VOLT?"))
+    }
+
+    pub fn get_current_setpoint(&mut self, ch: Channel) -> Result<f32> {
+        self.with_channel(ch, |k| k.query_number("CURR?"))
+    }
+
     pub fn enable_output(&mut self, state: State) -> Result<()> {
         let cmd = format!("OUTP:ENAB {}", state);
-        self.inner.write(&cmd)?;
+        self.command(&cmd)?;
+        Ok(())
+    }
+
+    /// Sets how long the outputs stay on before the instrument switches them off.
+    pub fn set_output_timer(&mut self, delay: std::time::Duration) -> Result<()> {
+        check_range(
+            "output timer (s)",
+            delay.as_secs_f32(),
+            OUTPUT_TIMER_MIN.as_secs_f32(),
+            OUTPUT_TIMER_MAX.as_secs_f32(),
+        )?;
+        let cmd = format!("OUTP:TIM:DEL {:.1}", delay.as_secs_f32());
+        self.command(&cmd)?;
+        Ok(())
+    }
+
+    pub fn get_output_timer(&mut self) -> Result<std::time::Duration> {
+        let secs = self.query_number("OUTP:TIM:DEL?")?;
+        Ok(std::time::Duration::from_millis(
+            (secs.max(0.0) * 1000.0).round() as u64,
+        ))
+    }
+
+    pub fn enable_output_timer(&mut self, state: State) -> Result<()> {
+        let cmd = format!("OUTP:TIM:STAT {}", state);
+        self.command(&cmd)?;
         Ok(())
     }
 
+    pub fn output_timer_state(&mut self) -> Result<State> {
+        self.query_state("OUTP:TIM:STAT?")
+    }
+
     pub fn enable_channel(&mut self, ch: Channel, state: State) -> Result<()> {
+        self.with_channel(ch, |k| k.command(&format!("CHAN:OUTP {}", state)))
+    }
+
+    /// Runs `f` with `ch` selected, then restores the previously selected channel.
+    pub(crate) fn with_channel<R>(
+        &mut self,
+        ch: Channel,
+        f: impl FnOnce(&mut Self) -> Result<R>,
+    ) -> Result<R> {
         let prev_ch = self.get_channel()?;
         self.select_channel(ch)?;
-        let cmd = format!("CHAN:OUTP {}", state);
-        self.inner.write(&cmd)?;
+        let result = f(self);
         self.select_channel(prev_ch)?;
-        Ok(())
+        result
     }
 
     pub fn get_channel(&mut self) -> Result<Channel> {
-        self.inner.write("INST?")?;
-        let ch = self.inner.read()?;
+        let ch = self.inner.query("INST?")?;
         let ch = Channel::from_str(&ch)?;
         Ok(ch)
     }
 
     pub fn select_channel(&mut self, ch: Channel) -> Result<()> {
+        self.channel_limits(ch)?;
         let cmd = format!("INST {}", ch);
-        self.inner.write(&cmd)?;
+        self.command(&cmd)?;
         Ok(())
     }
 
     pub fn front_panel_ctrl(&mut self) -> Result<()> {
-        self.inner.write("SYST:LOC")?;
+        self.command("SYST:LOC")?;
         Ok(())
     }
 
     pub fn remote_ctrl(&mut self) -> Result<()> {
-        self.inner.write("SYST:REM")?;
+        self.command("SYST:REM")?;
         Ok(())
     }
 
     pub fn read_i(&mut self) -> Result<(f32, f32, f32)> {
-        self.inner.write("FETC:CURR? ALL")?;
-        let response = self.inner.read()?;
-
-        let response = response
-            .split(',')
-            .map(|x| x.trim().parse::<f32>().ok().unwrap_or(0.0))
-            .collect::<Vec<f32>>();
-
-        if response.len() == 3 {
-            Ok((response[0], response[1], response[2]))
-        } else {
-            Ok((0.0, 0.0, 0.0))
-        }
+        self.query_triple("FETC:CURR? ALL")
     }
 
     pub fn read_v(&mut self) -> Result<(f32, f32, f32)> {
-        self.inner.write("FETC:VOLT? ALL")?;
-        let response = self.inner.read()?;
-
-        let response = response
-            .split(',')
-            .map(|x| x.trim().parse::<f32>().ok().unwrap_or(0.0))
-            .collect::<Vec<f32>>();
-
-        if response.len() == 3 {
-            Ok((response[0], response[1], response[2]))
-        } else {
-            Ok((0.0, 0.0, 0.0))
-        }
+        self.query_triple("FETC:VOLT? ALL")
     }
 
     pub fn read_p(&mut self) -> Result<(f32, f32, f32)> {
-        self.inner.write("FETC:POW? ALL")?;
-        let response = self.inner.read()?;
-
-        let response = response
-            .split(',')
-            .map(|x| x.trim().parse::<f32>().ok().unwrap_or(0.0))
-            .collect::<Vec<f32>>();
-
-        if response.len() == 3 {
-            Ok((response[0], response[1], response[2]))
-        } else {
-            Ok((0.0, 0.0, 0.0))
-        }
+        self.query_triple("FETC:POW? ALL")
+    }
+
+    pub(crate) fn query_number(&mut self, cmd: &str) -> Result<f32> {
+        let response = self.inner.query(cmd)?;
+        response
+            .trim()
+            .parse::<f32>()
+            .map_err(|_| Error::parse_response(cmd, &response))
+    }
+
+    pub(crate) fn query_state(&mut self, cmd: &str) -> Result<State> {
+        let response = self.inner.query(cmd)?;
+        State::from_str(response.trim()).map_err(|_| Error::parse_response(cmd, &response))
+    }
+
+    fn query_triple(&mut self, cmd: &str) -> Result<(f32, f32, f32)> {
+        let response = self.inner.query(cmd)?;
+        parse_triple(cmd, &response, self.info.channels.len())
     }
 
     pub fn read_all(&mut self) -> Result<Meas> {
@@ -174,15 +564,317 @@ impl Keithley2230 {
         Ok(meas)
     }
 
+    /// Reads V, I and P of every channel in one bus round trip, so the values
+    /// of a channel belong together.
+    ///
+    /// `mode` only applies to the voltage query; current and power are always
+    /// fetched from that same acquisition.
+    pub fn read_all_combined(&mut self, mode: MeasureMode) -> Result<Meas> {
+        let cmd = combined_query(mode);
+        let response = self.inner.query(&cmd)?;
+        parse_combined(&cmd, &response, self.info.channels.len())
+    }
+
     pub fn set_paralel(&mut self, state: State) -> Result<()> {
         let cmd = format!("OUT:PAR {}", state);
-        self.inner.write(&cmd)?;
+        self.command(&cmd)?;
         Ok(())
     }
 
     pub fn set_series(&mut self, state: State) -> Result<()> {
         let cmd = format!("OUT:SER {}", state);
-        self.inner.write(&cmd)?;
+        self.command(&cmd)?;
         Ok(())
     }
+
+    pub fn set_tracking(&mut self, state: State) -> Result<()> {
+        let cmd = format!("OUT:TRAC {}", state);
+        self.command(&cmd)?;
+        Ok(())
+    }
+
+    pub fn output_state(&mut self) -> Result<State> {
+        self.query_state("OUTP:ENAB?")
+    }
+
+    pub fn channel_state(&mut self, ch: Channel) -> Result<State> {
+        self.with_channel(ch, |k| k.query_state("CHAN:OUTP?"))
+    }
+
+    pub fn parallel_state(&mut self) -> Result<State> {
+        self.query_state("OUT:PAR?")
+    }
+
+    pub fn series_state(&mut self) -> Result<State> {
+        self.query_state("OUT:SER?")
+    }
+
+    pub fn tracking_state(&mut self) -> Result<State> {
+        self.query_state("OUT:TRAC?")
+    }
+}
+
+/// Picks the value belonging to `ch` out of an `ALL` reading.
+pub(crate) fn channel_value(values: (f32, f32, f32), ch: Channel) -> f32 {
+    match ch {
+        Channel::CH1 => values.0,
+        Channel::CH2 => values.1,
+        Channel::CH3 => values.2,
+    }
+}
+
+/// Parses a comma separated `ALL` response holding one value per channel.
+///
+/// Two channel models answer with two values; the missing CH3 reads as 0.0.
+fn parse_triple(command: &str, raw: &str, channels: usize) -> Result<(f32, f32, f32)> {
+    let values = raw
+        .split(',')
+        .map(|x| x.trim().parse::<f32>())
+        .collect::<std::result::Result<Vec<f32>, _>>()
+        .map_err(|_| Error::parse_response(command, raw))?;
+
+    match values[..] {
+        [a, b, c] if channels == 3 => Ok((a, b, c)),
+        [a, b] if channels == 2 => Ok((a, b, 0.0)),
+        _ => Err(Error::parse_response(command, raw)),
+    }
+}
+
+/// One acquisition for all three lists: a `MEAS` voltage query triggers it and
+/// the `FETC` queries after it read the same sample.
+fn combined_query(mode: MeasureMode) -> String {
+    format!("{}:VOLT? ALL;:FETC:CURR? ALL;:FETC:POW? ALL", mode)
+}
+
+/// Parses the `;` separated V, I and P lists of a combined measurement query.
+fn parse_combined(command: &str, raw: &str, channels: usize) -> Result<Meas> {
+    let parts = raw.split(';').collect::<Vec<&str>>();
+    let [v, i, p] = parts[..] else {
+        return Err(Error::parse_response(command, raw));
+    };
+    let v = parse_triple(command, v, channels)?;
+    let i = parse_triple(command, i, channels)?;
+    let p = parse_triple(command, p, channels)?;
+
+    Ok(Meas {
+        ch1: ChMeas::new(v.0, i.0, p.0),
+        ch2: ChMeas::new(v.1, i.1, p.1),
+        ch3: ChMeas::new(v.2, i.2, p.2),
+    })
+}
+
+/// Parses an `APPL?` response, e.g. `5.000, 1.000` or `CH1,5.000V,1.000A`.
+fn parse_setpoint(command: &str, raw: &str) -> Result<Setpoint> {
+    let values = raw
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| Channel::from_str(x).is_err())
+        .map(|x| x.trim_end_matches(['V', 'A', 'v', 'a']).parse::<f32>())
+        .collect::<std::result::Result<Vec<f32>, _>>()
+        .map_err(|_| Error::parse_response(command, raw))?;
+
+    match values[..] {
+        [voltage, current] => Ok(Setpoint { voltage, current }),
+        _ => Err(Error::parse_response(command, raw)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_meas(m: &ChMeas, v: f32, i: f32) {
+        assert!((m.v - v).abs() < 1e-4, "v = {}, expected {}", m.v, v);
+        assert!((m.i - i).abs() < 1e-4, "i = {}, expected {}", m.i, i);
+        assert!(
+            (m.p - v * i).abs() < 1e-3,
+            "p = {}, expected {}",
+            m.p,
+            v * i
+        );
+    }
+
+    #[test]
+    fn setpoint_round_trip() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.set_channel(Channel::CH2, 12.5, 0.75).unwrap();
+        assert_eq!(
+            k.get_setpoint(Channel::CH2).unwrap(),
+            Setpoint::new(12.5, 0.75)
+        );
+        assert_eq!(k.get_voltage_setpoint(Channel::CH2).unwrap(), 12.5);
+        assert_eq!(k.get_current_setpoint(Channel::CH2).unwrap(), 0.75);
+    }
+
+    #[test]
+    fn enable_channel_restores_selection() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.select_channel(Channel::CH2).unwrap();
+        k.enable_channel(Channel::CH3, State::ON).unwrap();
+
+        assert_eq!(k.channel_state(Channel::CH3).unwrap(), State::ON);
+        assert_eq!(k.channel_state(Channel::CH1).unwrap(), State::OFF);
+        assert_eq!(k.get_channel().unwrap(), Channel::CH2);
+    }
+
+    #[test]
+    fn read_all_follows_setpoints() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        // 10 ohm default load: CH1 regulates voltage, CH2 hits its current limit.
+        k.set_channel(Channel::CH1, 10.0, 3.0).unwrap();
+        k.set_channel(Channel::CH2, 30.0, 0.5).unwrap();
+        k.enable_channel(Channel::CH1, State::ON).unwrap();
+        k.enable_channel(Channel::CH2, State::ON).unwrap();
+
+        assert_eq!(k.read_all().unwrap(), Meas::default());
+        k.enable_output(State::ON).unwrap();
+        assert_eq!(k.output_state().unwrap(), State::ON);
+
+        let meas = k.read_all().unwrap();
+        assert_meas(&meas.ch1, 10.0, 1.0);
+        assert_meas(&meas.ch2, 5.0, 0.5);
+        assert_meas(&meas.ch3, 0.0, 0.0);
+    }
+
+    #[test]
+    fn instrument_errors_surface_when_checking() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.command("VOLT 99").unwrap();
+        let errors = k.read_error_queue().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, -222);
+        assert!(k.read_error_queue().unwrap().is_empty());
+
+        k.set_error_checking(true);
+        assert!(matches!(k.command("VOLT 99"), Err(Error::Instrument(e)) if e.code == -222));
+        k.command("VOLT 1").unwrap();
+    }
+
+    #[test]
+    fn scpi_error_from_str() {
+        let e = ScpiError::from_str(r#"-222,"Data out of range""#).unwrap();
+        assert_eq!((e.code, e.message.as_str()), (-222, "Data out of range"));
+        let e = ScpiError::from_str(r#" 0 , "No error" "#).unwrap();
+        assert_eq!((e.code, e.message.as_str()), (0, "No error"));
+        let e = ScpiError::from_str("-113").unwrap();
+        assert_eq!((e.code, e.message.as_str()), (-113, ""));
+
+        for raw in ["", "garbage", r#""No error",0"#, "1.5,\"x\""] {
+            assert!(
+                matches!(ScpiError::from_str(raw), Err(Error::ParseResponse { command, .. }) if command == "SYST:ERR?"),
+                "{:?} should not parse",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn parse_triple_values() {
+        let cmd = "FETC:VOLT? ALL";
+        assert_eq!(parse_triple(cmd, "1.0, 2.5,3", 3).unwrap(), (1.0, 2.5, 3.0));
+        assert_eq!(parse_triple(cmd, "1.0,2.5", 2).unwrap(), (1.0, 2.5, 0.0));
+        for (raw, channels) in [
+            ("1.0,2.5", 3),
+            ("1.0,2.5,3", 2),
+            ("1.0,x,3", 3),
+            ("", 3),
+            ("1,2,3,4", 3),
+        ] {
+            assert!(
+                matches!(
+                    parse_triple(cmd, raw, channels),
+                    Err(Error::ParseResponse { command, raw: r }) if command == cmd && r == raw
+                ),
+                "{:?} should not parse",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn two_channel_model_rejects_ch3() {
+        let mut k = Keithley2230::simulated(Model::K2220_30_1);
+        let unsupported = |r: Result<()>| matches!(r, Err(Error::UnsupportedChannel(Channel::CH3)));
+
+        assert!(unsupported(k.set_channel(Channel::CH3, 1.0, 0.1)));
+        assert!(unsupported(k.select_channel(Channel::CH3)));
+        assert!(unsupported(k.enable_channel(Channel::CH3, State::ON)));
+        assert!(matches!(
+            k.get_setpoint(Channel::CH3),
+            Err(Error::UnsupportedChannel(Channel::CH3))
+        ));
+        // CH3 still reads as zero so the tuple shape is the same on every model.
+        assert_eq!(k.read_v().unwrap().2, 0.0);
+    }
+
+    #[test]
+    fn setpoints_are_checked_against_model_limits() {
+        let mut k = Keithley2230::simulated(Model::K2220_30_1);
+        assert!(matches!(
+            k.set_channel(Channel::CH1, 5.0, 1.6),
+            Err(Error::OutOfRange { max, .. }) if max == 1.5
+        ));
+        k.set_channel(Channel::CH1, 30.0, 1.5).unwrap();
+
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        assert!(k.set_channel(Channel::CH3, 6.5, 1.0).is_err());
+        assert!(k.set_channel(Channel::CH1, f32::NAN, 1.0).is_err());
+        assert!(k.set_channel(Channel::CH1, -0.1, 1.0).is_err());
+    }
+
+    #[test]
+    fn parse_setpoint_formats() {
+        let cmd = "APPL? CH1";
+        assert_eq!(
+            parse_setpoint(cmd, "5.000, 1.000").unwrap(),
+            Setpoint::new(5.0, 1.0)
+        );
+        assert_eq!(
+            parse_setpoint(cmd, "CH1,5.000V,1.000A").unwrap(),
+            Setpoint::new(5.0, 1.0)
+        );
+        for raw in ["5.000", "5,1,2", "abc,1.0", "", "CH1"] {
+            assert!(
+                matches!(parse_setpoint(cmd, raw), Err(Error::ParseResponse { .. })),
+                "{:?} should not parse",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn parse_combined_values() {
+        let cmd = "FETC:VOLT? ALL;:FETC:CURR? ALL;:FETC:POW? ALL";
+        let meas = parse_combined(cmd, "1,2,3;0.1,0.2,0.3;0.1,0.4,0.9", 3).unwrap();
+        assert_eq!(meas.ch2, ChMeas::new(2.0, 0.2, 0.4));
+        assert!(parse_combined(cmd, "1,2,3;0.1,0.2,0.3", 3).is_err());
+        assert!(parse_combined(cmd, "1,2;0.1,0.2;0.1,0.4", 3).is_err());
+    }
+
+    #[test]
+    fn read_all_combined_is_one_acquisition() {
+        assert_eq!(
+            combined_query(MeasureMode::Measure),
+            "MEAS:VOLT? ALL;:FETC:CURR? ALL;:FETC:POW? ALL"
+        );
+        assert_eq!(
+            combined_query(MeasureMode::Fetch),
+            "FETC:VOLT? ALL;:FETC:CURR? ALL;:FETC:POW? ALL"
+        );
+
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.set_channel(Channel::CH1, 10.0, 3.0).unwrap();
+        k.enable_channel(Channel::CH1, State::ON).unwrap();
+        k.enable_output(State::ON).unwrap();
+        let meas = k.read_all().unwrap();
+        for mode in [MeasureMode::Fetch, MeasureMode::Measure] {
+            assert_eq!(k.read_all_combined(mode).unwrap(), meas);
+        }
+
+        let mut k = Keithley2230::simulated(Model::K2220_30_1);
+        assert_eq!(
+            k.read_all_combined(MeasureMode::Measure).unwrap(),
+            Meas::default()
+        );
+    }
 }
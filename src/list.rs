@@ -0,0 +1,311 @@
+use crate::{check_range, Channel, Error, Keithley2230, ModelInfo, Result, State, Transport};
+use std::time::Duration;
+
+/// One point of a stepped list: hold `voltage`/`current` for `dwell`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListStep {
+    pub voltage: f32,
+    pub current: f32,
+    pub dwell: Duration,
+}
+
+/// Stepped voltage/current program for one channel of a 2230G/2220G.
+///
+/// ```no_run
+/// # use keithley_2230_series::*;
+/// # use std::time::Duration;
+/// let seq = ListSequence::new(Channel::CH1)
+///     .step(3.3, 0.5, Duration::from_secs(1))
+///     .step(5.0, 0.5, Duration::from_secs(2))
+///     .repeat(10);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListSequence {
+    pub channel: Channel,
+    pub steps: Vec<ListStep>,
+    pub repeat: u32,
+}
+
+/// Non-volatile list storage location on the instrument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+pub struct ListSlot(u8);
+
+impl TryFrom<u8> for ListSlot {
+    type Error = Error;
+
+    fn try_from(slot: u8) -> Result<Self> {
+        Self::new(slot)
+    }
+}
+
+impl From<ListSlot> for u8 {
+    fn from(slot: ListSlot) -> Self {
+        slot.0
+    }
+}
+
+impl ListSlot {
+    pub const MIN: u8 = 1;
+    pub const MAX: u8 = 10;
+
+    pub fn new(slot: u8) -> Result<Self> {
+        check_range("list slot", slot.into(), Self::MIN.into(), Self::MAX.into())?;
+        Ok(Self(slot))
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+impl ListSequence {
+    pub const MAX_STEPS: usize = 80;
+    pub const MIN_DWELL: Duration = Duration::from_millis(100);
+    pub const MAX_DWELL: Duration = Duration::from_secs(3600);
+    pub const MAX_REPEAT: u32 = 9999;
+
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            channel,
+            steps: Vec::new(),
+            repeat: 1,
+        }
+    }
+
+    pub fn step(mut self, voltage: f32, current: f32, dwell: Duration) -> Self {
+        self.steps.push(ListStep {
+            voltage,
+            current,
+            dwell,
+        });
+        self
+    }
+
+    pub fn repeat(mut self, count: u32) -> Self {
+        self.repeat = count;
+        self
+    }
+
+    /// Total run time including repeats.
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|s| s.dwell).sum::<Duration>() * self.repeat
+    }
+
+    /// Checks step count, dwell times, repeat count and setpoints against `info`.
+    pub fn validate(&self, info: &ModelInfo) -> Result<()> {
+        if !info.features.list_mode {
+            return Err(Error::UnsupportedFeature("list mode"));
+        }
+        let limits = info
+            .channel_limits(self.channel)
+            .ok_or(Error::UnsupportedChannel(self.channel))?;
+        check_range(
+            "list step count",
+            self.steps.len() as f32,
+            1.0,
+            Self::MAX_STEPS as f32,
+        )?;
+        check_range(
+            "list repeat count",
+            self.repeat as f32,
+            1.0,
+            Self::MAX_REPEAT as f32,
+        )?;
+        for (n, step) in self.steps.iter().enumerate() {
+            let name = format!("{} list step {}", self.channel, n + 1);
+            check_range(
+                &format!("{} voltage", name),
+                step.voltage,
+                0.0,
+                limits.max_voltage,
+            )?;
+            check_range(
+                &format!("{} current", name),
+                step.current,
+                0.0,
+                limits.max_current,
+            )?;
+            check_range(
+                &format!("{} dwell (s)", name),
+                step.dwell.as_secs_f32(),
+                Self::MIN_DWELL.as_secs_f32(),
+                Self::MAX_DWELL.as_secs_f32(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Transport> Keithley2230<T> {
+    /// Validates `seq` and loads it into the working list of its channel.
+    pub fn upload_list(&mut self, seq: &ListSequence) -> Result<()> {
+        seq.validate(&self.info)?;
+        self.with_channel(seq.channel, |k| {
+            k.command(&format!("LIST:STEP {}", seq.steps.len()))?;
+            for (n, step) in seq.steps.iter().enumerate() {
+                k.command(&format!(
+                    "LIST:UNIT {}, {}, {}, {}",
+                    n + 1,
+                    step.voltage,
+                    step.current,
+                    step.dwell.as_secs_f32()
+                ))?;
+            }
+            k.command(&format!("LIST:COUN {}", seq.repeat))
+        })
+    }
+
+    /// Stores the working list of `ch` in `slot`.
+    pub fn save_list(&mut self, ch: Channel, slot: ListSlot) -> Result<()> {
+        self.require_list_mode()?;
+        self.with_channel(ch, |k| k.command(&format!("LIST:SAVE {}", slot.get())))
+    }
+
+    /// Loads `slot` into the working list of `ch`.
+    pub fn recall_list(&mut self, ch: Channel, slot: ListSlot) -> Result<()> {
+        self.require_list_mode()?;
+        self.with_channel(ch, |k| k.command(&format!("LIST:RCL {}", slot.get())))
+    }
+
+    pub fn start_list(&mut self, ch: Channel) -> Result<()> {
+        self.require_list_mode()?;
+        self.with_channel(ch, |k| k.command(&format!("LIST:STAT {}", State::ON)))
+    }
+
+    pub fn stop_list(&mut self, ch: Channel) -> Result<()> {
+        self.require_list_mode()?;
+        self.with_channel(ch, |k| k.command(&format!("LIST:STAT {}", State::OFF)))
+    }
+
+    pub fn list_running(&mut self, ch: Channel) -> Result<bool> {
+        self.require_list_mode()?;
+        let state = self.with_channel(ch, |k| k.query_state("LIST:STAT?"))?;
+        Ok(state == State::ON)
+    }
+
+    fn require_list_mode(&self) -> Result<()> {
+        if self.info.features.list_mode {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedFeature("list mode"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Model, Setpoint};
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn rejected(seq: &ListSequence, info: &ModelInfo, parameter: &str) {
+        match seq.validate(info) {
+            Err(Error::OutOfRange { parameter: p, .. }) => assert!(
+                p.contains(parameter),
+                "{:?} rejected for {:?}, expected {:?}",
+                seq,
+                p,
+                parameter
+            ),
+            other => panic!("{:?} gave {:?}, expected {:?}", seq, other, parameter),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_sequences() {
+        let info = ModelInfo::new(Model::K2230G_30_1, "1", "1");
+        let seq = ListSequence::new(Channel::CH1).step(5.0, 1.0, ms(100));
+        seq.validate(&info).unwrap();
+
+        rejected(&ListSequence::new(Channel::CH1), &info, "step count");
+        let mut long = ListSequence::new(Channel::CH1);
+        long.steps = vec![seq.steps[0]; ListSequence::MAX_STEPS + 1];
+        rejected(&long, &info, "step count");
+
+        rejected(&seq.clone().step(5.0, 1.0, ms(99)), &info, "dwell");
+        rejected(&seq.clone().step(5.0, 1.0, ms(3_600_001)), &info, "dwell");
+        rejected(&seq.clone().repeat(0), &info, "repeat count");
+        rejected(&seq.clone().repeat(10_000), &info, "repeat count");
+        rejected(
+            &seq.clone().step(30.1, 1.0, ms(100)),
+            &info,
+            "step 2 voltage",
+        );
+
+        let mut ch3 = seq.clone();
+        ch3.channel = Channel::CH3;
+        let info = ModelInfo::new(Model::K2220G_30_1, "1", "1");
+        assert!(matches!(
+            ch3.validate(&info),
+            Err(Error::UnsupportedChannel(Channel::CH3))
+        ));
+    }
+
+    #[test]
+    fn list_mode_needs_a_g_model() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        let seq = ListSequence::new(Channel::CH1).step(5.0, 1.0, ms(100));
+        let slot = ListSlot::new(1).unwrap();
+        let unsupported = |r: Result<()>| matches!(r, Err(Error::UnsupportedFeature(_)));
+
+        assert!(unsupported(k.upload_list(&seq)));
+        assert!(unsupported(k.save_list(Channel::CH1, slot)));
+        assert!(unsupported(k.recall_list(Channel::CH1, slot)));
+        assert!(unsupported(k.start_list(Channel::CH1)));
+        assert!(unsupported(k.stop_list(Channel::CH1)));
+        assert!(unsupported(k.list_running(Channel::CH1).map(|_| ())));
+    }
+
+    #[test]
+    fn upload_start_and_run_to_completion() {
+        let mut k = Keithley2230::simulated(Model::K2230G_30_1);
+        k.set_error_checking(true);
+        let seq = ListSequence::new(Channel::CH2)
+            .step(3.3, 0.5, ms(100))
+            .step(5.0, 0.25, ms(100));
+        k.upload_list(&seq).unwrap();
+
+        assert!(!k.list_running(Channel::CH2).unwrap());
+        k.start_list(Channel::CH2).unwrap();
+        assert!(k.list_running(Channel::CH2).unwrap());
+        assert_eq!(k.get_setpoint(Channel::CH2).unwrap().voltage, 3.3);
+        // Driving the list must not move the front panel selection.
+        assert_eq!(k.get_channel().unwrap(), Channel::CH1);
+
+        std::thread::sleep(seq.duration() + ms(100));
+        assert!(!k.list_running(Channel::CH2).unwrap());
+        assert_eq!(
+            k.get_setpoint(Channel::CH2).unwrap(),
+            Setpoint::new(5.0, 0.25)
+        );
+    }
+
+    #[test]
+    fn save_and_recall_list() {
+        let mut k = Keithley2230::simulated(Model::K2230G_30_1);
+        k.set_error_checking(true);
+        let slot = ListSlot::new(2).unwrap();
+        let first = ListSequence::new(Channel::CH1).step(1.5, 0.5, ms(1000));
+        let second = ListSequence::new(Channel::CH1).step(2.5, 0.5, ms(1000));
+
+        k.upload_list(&first).unwrap();
+        k.save_list(Channel::CH1, slot).unwrap();
+        k.upload_list(&second).unwrap();
+        k.recall_list(Channel::CH1, slot).unwrap();
+
+        k.start_list(Channel::CH1).unwrap();
+        assert_eq!(k.get_setpoint(Channel::CH1).unwrap().voltage, 1.5);
+        k.stop_list(Channel::CH1).unwrap();
+        assert!(!k.list_running(Channel::CH1).unwrap());
+
+        assert!(ListSlot::new(0).is_err());
+        assert!(ListSlot::new(11).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{Channel, Error, Keithley2230, Meas, MeasureMode, Result, Transport};
+use std::any::Any;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// When to start a new CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rotation {
+    #[default]
+    Never,
+    /// Once the current file has reached this many bytes.
+    BySize(u64),
+    /// Once the current file has been open this long.
+    ByTime(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoggerConfig {
+    /// First file to write; rotated files are numbered `name.1.csv`, `name.2.csv`, ...
+    /// Existing files are skipped rather than overwritten.
+    pub path: PathBuf,
+    pub interval: Duration,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rotation: Rotation,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: MeasureMode,
+}
+
+impl LoggerConfig {
+    pub fn new<P: Into<PathBuf>>(path: P, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            interval,
+            rotation: Rotation::Never,
+            mode: MeasureMode::Fetch,
+        }
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn mode(mut self, mode: MeasureMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// Samples [`Keithley2230::read_all_combined`] on a background thread and appends each
+/// sample to a CSV file.
+///
+/// The logger owns the instrument while it runs; [`DataLogger::stop`] hands it back.
+pub struct DataLogger<T: Transport> {
+    thread: JoinHandle<(Keithley2230<T>, Result<()>)>,
+    stop: Arc<AtomicBool>,
+}
+
+impl<T: Transport + Send + 'static> DataLogger<T> {
+    pub fn start(k: Keithley2230<T>, config: LoggerConfig) -> Result<Self> {
+        let channels = k.model_info().channels.clone();
+        let writer = CsvWriter::create(config.path.clone(), &channels)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let mut k = k;
+            // Catch panics here so stop() can still hand the instrument back.
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| run(&mut k, writer, &config, &flag)))
+                    .unwrap_or_else(|payload| Err(Error::LoggerPanicked(panic_message(payload))));
+            (k, result)
+        });
+        Ok(Self { thread, stop })
+    }
+}
+
+impl<T: Transport> DataLogger<T> {
+    /// `false` once sampling has stopped, either on request or because of an error.
+    pub fn is_running(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    /// Stops sampling and returns the instrument along with the first error
+    /// that ended logging early, if any. A panic while sampling is reported as
+    /// [`Error::LoggerPanicked`].
+    pub fn stop(self) -> (Keithley2230<T>, Result<()>) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.thread().unpark();
+        match self.thread.join() {
+            Ok(stopped) => stopped,
+            // Sampling panics are caught on the thread; nothing else there can unwind.
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+fn run<T: Transport>(
+    k: &mut Keithley2230<T>,
+    mut writer: CsvWriter,
+    config: &LoggerConfig,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let start = Instant::now();
+    let mut next = start;
+    while !stop.load(Ordering::SeqCst) {
+        let meas = k.read_all_combined(config.mode)?;
+        let row = csv_row(&writer.channels, SystemTime::now(), start.elapsed(), &meas);
+        writer.write_row(&row)?;
+        if writer.should_rotate(config.rotation) {
+            writer = writer.rotate()?;
+        }
+
+        next += config.interval;
+        // Park rather than sleep so stop() can wake us immediately.
+        while !stop.load(Ordering::SeqCst) {
+            match next.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => std::thread::park_timeout(remaining),
+                _ => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+/// CSV header naming the columns written by [`csv_row`]:
+/// `unix_time,elapsed_s,ch1_v,ch1_i,ch1_p,...` for each of `channels`.
+pub fn csv_header(channels: &[Channel]) -> String {
+    let mut header = vec!["unix_time".to_string(), "elapsed_s".to_string()];
+    for ch in channels {
+        let ch = ch.as_ref().to_lowercase();
+        header.extend(["v", "i", "p"].map(|q| format!("{}_{}", ch, q)));
+    }
+    header.join(",")
+}
+
+/// One CSV line of `meas`, taken at `at`, `elapsed` after logging started.
+pub fn csv_row(channels: &[Channel], at: SystemTime, elapsed: Duration, meas: &Meas) -> String {
+    let unix_time = at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    let mut row = vec![
+        format!("{:.3}", unix_time),
+        format!("{:.3}", elapsed.as_secs_f64()),
+    ];
+    for &ch in channels {
+        let m = meas.channel(ch);
+        row.extend([m.v, m.i, m.p].map(|x| x.to_string()));
+    }
+    row.join(",")
+}
+
+struct CsvWriter {
+    base: PathBuf,
+    index: u32,
+    channels: Vec<Channel>,
+    file: BufWriter<File>,
+    written: u64,
+    opened: Instant,
+}
+
+impl CsvWriter {
+    fn create(base: PathBuf, channels: &[Channel]) -> Result<Self> {
+        Self::open(base, 0, channels.to_vec())
+    }
+
+    /// Opens the first of `index`, `index + 1`, ... that doesn't exist yet, so
+    /// a restarted logger never overwrites earlier files.
+    fn open(base: PathBuf, mut index: u32, channels: Vec<Channel>) -> Result<Self> {
+        let file = loop {
+            let path = rotated_path(&base, index);
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(file) => break BufWriter::new(file),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => index += 1,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        let mut writer = Self {
+            base,
+            index,
+            channels,
+            file,
+            written: 0,
+            opened: Instant::now(),
+        };
+        let header = csv_header(&writer.channels);
+        writer.write_row(&header)?;
+        Ok(writer)
+    }
+
+    fn write_row(&mut self, row: &str) -> Result<()> {
+        writeln!(self.file, "{}", row)?;
+        // Flush every row so a crashed host still leaves a usable log.
+        self.file.flush()?;
+        self.written += row.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn should_rotate(&self, rotation: Rotation) -> bool {
+        match rotation {
+            Rotation::Never => false,
+            Rotation::BySize(bytes) => self.written >= bytes,
+            Rotation::ByTime(age) => self.opened.elapsed() >= age,
+        }
+    }
+
+    fn rotate(self) -> Result<Self> {
+        Self::open(self.base, self.index + 1, self.channels)
+    }
+}
+
+/// `log.csv` -> `log.csv`, `log.1.csv`, `log.2.csv`, ...
+fn rotated_path(base: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        return base.to_path_buf();
+    }
+    let stem = base.file_stem().and_then(|s| s.to_str()).unwrap_or("log");
+    let name = match base.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, index, ext),
+        None => format!("{}.{}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("k2230-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn log_once(path: &Path) {
+        let k = Keithley2230::simulated(Model::K2230_30_1);
+        let config = LoggerConfig::new(path, Duration::from_millis(10));
+        let logger = DataLogger::start(k, config).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        let (_, result) = logger.stop();
+        result.unwrap();
+    }
+
+    #[test]
+    fn restart_does_not_overwrite_earlier_logs() {
+        let dir = scratch_dir("restart");
+        let path = dir.join("log.csv");
+        log_once(&path);
+        let first = std::fs::read_to_string(&path).unwrap();
+        log_once(&path);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), first);
+        let second = std::fs::read_to_string(dir.join("log.1.csv")).unwrap();
+        assert!(second.starts_with("unix_time,elapsed_s,ch1_v"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Panics as soon as it is asked for a measurement.
+    struct PanicOnFetch(crate::Simulator);
+
+    impl Transport for PanicOnFetch {
+        fn write(&mut self, command: &str) -> Result<()> {
+            assert!(!command.starts_with("FETC"), "bus exploded");
+            self.0.write(command)
+        }
+
+        fn read(&mut self) -> Result<String> {
+            self.0.read()
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.0.clear()
+        }
+    }
+
+    #[test]
+    fn sampling_panic_is_returned_as_error() {
+        let dir = scratch_dir("panic");
+        let k =
+            Keithley2230::with_transport(PanicOnFetch(crate::Simulator::new(Model::K2230_30_1)))
+                .unwrap();
+        let config = LoggerConfig::new(dir.join("log.csv"), Duration::from_millis(10));
+        let logger = DataLogger::start(k, config).unwrap();
+        while logger.is_running() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let (mut k, result) = logger.stop();
+        assert!(matches!(result, Err(Error::LoggerPanicked(m)) if m == "bus exploded"));
+        // The instrument is still usable afterwards.
+        assert_eq!(k.get_channel().unwrap(), Channel::CH1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotated_names_keep_the_extension() {
+        let base = Path::new("/data/log.csv");
+        assert_eq!(rotated_path(base, 0), Path::new("/data/log.csv"));
+        assert_eq!(rotated_path(base, 2), Path::new("/data/log.2.csv"));
+        assert_eq!(rotated_path(Path::new("log"), 1), Path::new("log.1"));
+    }
+
+    #[test]
+    fn csv_columns_match_header() {
+        let channels = [Channel::CH1, Channel::CH2];
+        assert_eq!(
+            csv_header(&channels),
+            "unix_time,elapsed_s,ch1_v,ch1_i,ch1_p,ch2_v,ch2_i,ch2_p"
+        );
+
+        let meas = Meas {
+            ch2: crate::ChMeas::new(5.0, 0.5, 2.5),
+            ..Meas::default()
+        };
+        let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        assert_eq!(
+            csv_row(&channels, at, Duration::from_millis(1500), &meas),
+            "1700000000.250,1.500,0,0,0,5,0.5,2.5"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{check_range, Channel, Error, Keithley2230, Result, Setpoint, Transport};
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// Setup memory location used by `*SAV` / `*RCL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+pub struct MemorySlot(u8);
+
+impl TryFrom<u8> for MemorySlot {
+    type Error = Error;
+
+    fn try_from(slot: u8) -> Result<Self> {
+        Self::new(slot)
+    }
+}
+
+impl From<MemorySlot> for u8 {
+    fn from(slot: MemorySlot) -> Self {
+        slot.0
+    }
+}
+
+impl MemorySlot {
+    pub const MIN: u8 = 1;
+    pub const MAX: u8 = 36;
+
+    pub fn new(slot: u8) -> Result<Self> {
+        check_range(
+            "memory slot",
+            slot.into(),
+            Self::MIN.into(),
+            Self::MAX.into(),
+        )?;
+        Ok(Self(slot))
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+/// What was stored in a slot when it was saved through [`Keithley2230::save_named`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlotEntry {
+    pub slot: MemorySlot,
+    pub description: String,
+    pub saved_at: SystemTime,
+    pub setpoints: Vec<(Channel, Setpoint)>,
+}
+
+/// Host-side map from operator friendly names to instrument memory slots.
+///
+/// A slot belongs to at most one name; saving under a new name drops the old one.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct SlotRegistry {
+    entries: BTreeMap<String, SlotEntry>,
+}
+
+impl SlotRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SlotEntry> {
+        self.entries.get(name)
+    }
+
+    /// Returns the name currently bound to `slot`, if any.
+    pub fn name_of(&self, slot: MemorySlot) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, e)| e.slot == slot)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn insert(&mut self, name: &str, entry: SlotEntry) -> Option<SlotEntry> {
+        self.entries
+            .retain(|n, e| n == name || e.slot != entry.slot);
+        self.entries.insert(name.to_string(), entry)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<SlotEntry> {
+        self.entries.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SlotEntry)> {
+        self.entries.iter().map(|(name, e)| (name.as_str(), e))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<T: Transport> Keithley2230<T> {
+    pub fn save_state(&mut self, slot: MemorySlot) -> Result<()> {
+        self.command(&format!("*SAV {}", slot.get()))
+    }
+
+    pub fn recall_state(&mut self, slot: MemorySlot) -> Result<()> {
+        self.command(&format!("*RCL {}", slot.get()))
+    }
+
+    /// Saves the current setup to `slot` and records it in `registry` under `name`.
+    pub fn save_named(
+        &mut self,
+        registry: &mut SlotRegistry,
+        name: &str,
+        slot: MemorySlot,
+        description: &str,
+    ) -> Result<()> {
+        let mut setpoints = Vec::new();
+        for ch in self.info.channels.clone() {
+            setpoints.push((ch, self.get_setpoint(ch)?));
+        }
+        self.save_state(slot)?;
+        registry.insert(
+            name,
+            SlotEntry {
+                slot,
+                description: description.to_string(),
+                saved_at: SystemTime::now(),
+                setpoints,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn recall_named(&mut self, registry: &SlotRegistry, name: &str) -> Result<()> {
+        let entry = registry
+            .get(name)
+            .ok_or_else(|| Error::UnknownSlotName(name.to_string()))?;
+        self.recall_state(entry.slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    fn entry(slot: u8) -> SlotEntry {
+        SlotEntry {
+            slot: MemorySlot::new(slot).unwrap(),
+            description: String::new(),
+            saved_at: SystemTime::UNIX_EPOCH,
+            setpoints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn slot_belongs_to_one_name() {
+        let mut registry = SlotRegistry::new();
+        assert!(registry.insert("bench", entry(3)).is_none());
+        assert!(registry.insert("burn-in", entry(4)).is_none());
+
+        // Re-saving slot 3 under a new name drops "bench".
+        registry.insert("idle", entry(3));
+        assert!(registry.get("bench").is_none());
+        assert_eq!(registry.name_of(MemorySlot::new(3).unwrap()), Some("idle"));
+        assert_eq!(registry.len(), 2);
+
+        // Moving a name to another slot replaces its old entry.
+        let old = registry.insert("idle", entry(5)).unwrap();
+        assert_eq!(old.slot.get(), 3);
+        assert_eq!(registry.name_of(MemorySlot::new(3).unwrap()), None);
+        assert_eq!(
+            registry.name_of(MemorySlot::new(4).unwrap()),
+            Some("burn-in")
+        );
+    }
+
+    #[test]
+    fn save_and_recall_named() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.set_error_checking(true);
+        let mut registry = SlotRegistry::new();
+        let slot = MemorySlot::new(3).unwrap();
+
+        k.set_channel(Channel::CH1, 1.0, 0.5).unwrap();
+        k.save_named(&mut registry, "low", slot, "1 V bring-up")
+            .unwrap();
+        let entry = registry.get("low").unwrap();
+        assert_eq!(entry.slot, slot);
+        assert_eq!(entry.description, "1 V bring-up");
+        assert_eq!(entry.setpoints[0], (Channel::CH1, Setpoint::new(1.0, 0.5)));
+
+        k.set_channel(Channel::CH1, 12.0, 2.0).unwrap();
+        k.recall_named(&registry, "low").unwrap();
+        assert_eq!(
+            k.get_setpoint(Channel::CH1).unwrap(),
+            Setpoint::new(1.0, 0.5)
+        );
+
+        assert!(matches!(
+            k.recall_named(&registry, "high"),
+            Err(Error::UnknownSlotName(name)) if name == "high"
+        ));
+        assert!(MemorySlot::new(0).is_err());
+        assert!(MemorySlot::new(37).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{check_range, Channel, Error, Result};
+use std::str::FromStr;
+
+/// Members of the 2230/2231/2220 family this crate knows how to drive.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, strum::AsRefStr, strum::Display, strum::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+#[allow(non_camel_case_types)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Model {
+    #[strum(serialize = "2230-30-1")]
+    #[cfg_attr(feature = "serde", serde(rename = "2230-30-1"))]
+    K2230_30_1,
+    #[strum(serialize = "2230G-30-1")]
+    #[cfg_attr(feature = "serde", serde(rename = "2230G-30-1"))]
+    K2230G_30_1,
+    #[strum(serialize = "2231A-30-3")]
+    #[cfg_attr(feature = "serde", serde(rename = "2231A-30-3"))]
+    K2231A_30_3,
+    #[strum(serialize = "2220-30-1")]
+    #[cfg_attr(feature = "serde", serde(rename = "2220-30-1"))]
+    K2220_30_1,
+    #[strum(serialize = "2220G-30-1")]
+    #[cfg_attr(feature = "serde", serde(rename = "2220G-30-1"))]
+    K2220G_30_1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelLimits {
+    pub channel: Channel,
+    pub max_voltage: f32,
+    pub max_current: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Features {
+    /// LIST / SEQuence programming (G models).
+    pub list_mode: bool,
+    pub remote_sense: bool,
+}
+
+impl Model {
+    pub fn limits(&self) -> Vec<ChannelLimits> {
+        let limits: &[(Channel, f32, f32)] = match self {
+            Model::K2230_30_1 | Model::K2230G_30_1 => &[
+                (Channel::CH1, 30.0, 3.0),
+                (Channel::CH2, 30.0, 3.0),
+                (Channel::CH3, 6.0, 5.0),
+            ],
+            Model::K2231A_30_3 => &[
+                (Channel::CH1, 30.0, 3.0),
+                (Channel::CH2, 30.0, 3.0),
+                (Channel::CH3, 5.0, 3.0),
+            ],
+            Model::K2220_30_1 | Model::K2220G_30_1 => {
+                &[(Channel::CH1, 30.0, 1.5), (Channel::CH2, 30.0, 1.5)]
+            }
+        };
+        limits
+            .iter()
+            .map(|&(channel, max_voltage, max_current)| ChannelLimits {
+                channel,
+                max_voltage,
+                max_current,
+            })
+            .collect()
+    }
+
+    pub fn features(&self) -> Features {
+        match self {
+            Model::K2230_30_1 => Features {
+                list_mode: false,
+                remote_sense: true,
+            },
+            Model::K2230G_30_1 => Features {
+                list_mode: true,
+                remote_sense: true,
+            },
+            Model::K2231A_30_3 | Model::K2220_30_1 => Features {
+                list_mode: false,
+                remote_sense: false,
+            },
+            Model::K2220G_30_1 => Features {
+                list_mode: true,
+                remote_sense: false,
+            },
+        }
+    }
+}
+
+/// What the connected unit reported in `*IDN?`, plus what that model supports.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelInfo {
+    pub model: Model,
+    pub serial: String,
+    pub firmware: String,
+    pub channels: Vec<Channel>,
+    pub limits: Vec<ChannelLimits>,
+    pub features: Features,
+}
+
+impl ModelInfo {
+    pub fn new(model: Model, serial: &str, firmware: &str) -> Self {
+        let limits = model.limits();
+        Self {
+            model,
+            serial: serial.to_string(),
+            firmware: firmware.to_string(),
+            channels: limits.iter().map(|l| l.channel).collect(),
+            limits,
+            features: model.features(),
+        }
+    }
+
+    /// Parses a `*IDN?` response such as
+    /// `Keithley instruments, 2230-30-1, 9030101, 1.16-1.04`.
+    pub fn from_idn(idn: &str) -> Result<Self> {
+        let fields = idn.split(',').map(|x| x.trim()).collect::<Vec<&str>>();
+        let [manufacturer, model, serial, firmware] = fields[..] else {
+            return Err(Error::parse_response("*IDN?", idn));
+        };
+        if !manufacturer.to_ascii_uppercase().contains("KEITHLEY") {
+            return Err(Error::UnsupportedModel(idn.to_string()));
+        }
+        let model =
+            Model::from_str(model).map_err(|_| Error::UnsupportedModel(model.to_string()))?;
+        Ok(Self::new(model, serial, firmware))
+    }
+
+    pub fn has_channel(&self, ch: Channel) -> bool {
+        self.channels.contains(&ch)
+    }
+
+    pub fn channel_limits(&self, ch: Channel) -> Option<&ChannelLimits> {
+        self.limits.iter().find(|l| l.channel == ch)
+    }
+
+    /// Checks `v`/`i` against the limits of `ch`.
+    pub fn validate_setpoint(&self, ch: Channel, v: f32, i: f32) -> Result<()> {
+        let limits = self
+            .channel_limits(ch)
+            .ok_or(Error::UnsupportedChannel(ch))?;
+        check_range(&format!("{} voltage", ch), v, 0.0, limits.max_voltage)?;
+        check_range(&format!("{} current", ch), i, 0.0, limits.max_current)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_per_model() {
+        let info = ModelInfo::new(Model::K2231A_30_3, "1", "1");
+        assert_eq!(info.channels, [Channel::CH1, Channel::CH2, Channel::CH3]);
+        let ch3 = info.channel_limits(Channel::CH3).unwrap();
+        assert_eq!((ch3.max_voltage, ch3.max_current), (5.0, 3.0));
+
+        let info = ModelInfo::new(Model::K2230G_30_1, "1", "1");
+        let ch3 = info.channel_limits(Channel::CH3).unwrap();
+        assert_eq!((ch3.max_voltage, ch3.max_current), (6.0, 5.0));
+
+        for model in [Model::K2220_30_1, Model::K2220G_30_1] {
+            let info = ModelInfo::new(model, "1", "1");
+            assert_eq!(info.channels, [Channel::CH1, Channel::CH2]);
+            assert!(!info.has_channel(Channel::CH3));
+            assert_eq!(info.channel_limits(Channel::CH1).unwrap().max_current, 1.5);
+        }
+    }
+
+    #[test]
+    fn from_idn() {
+        let info =
+            ModelInfo::from_idn("Keithley instruments, 2230G-30-1, 9030101, 1.16-1.04").unwrap();
+        assert_eq!(info.model, Model::K2230G_30_1);
+        assert_eq!(info.serial, "9030101");
+        assert_eq!(info.firmware, "1.16-1.04");
+        assert!(info.features.list_mode);
+
+        assert!(matches!(
+            ModelInfo::from_idn("KEITHLEY INSTRUMENTS,2400,1,1"),
+            Err(Error::UnsupportedModel(m)) if m == "2400"
+        ));
+        assert!(matches!(
+            ModelInfo::from_idn("Rigol,2230-30-1,1,1"),
+            Err(Error::UnsupportedModel(_))
+        ));
+        for idn in ["", "Keithley instruments, 2230-30-1, 9030101"] {
+            assert!(matches!(
+                ModelInfo::from_idn(idn),
+                Err(Error::ParseResponse { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_setpoint() {
+        let info = ModelInfo::new(Model::K2230_30_1, "1", "1");
+        info.validate_setpoint(Channel::CH3, 6.0, 5.0).unwrap();
+        assert!(matches!(
+            info.validate_setpoint(Channel::CH3, 6.1, 1.0),
+            Err(Error::OutOfRange { max, .. }) if max == 6.0
+        ));
+        assert!(info.validate_setpoint(Channel::CH1, 1.0, -0.1).is_err());
+
+        let info = ModelInfo::new(Model::K2220_30_1, "1", "1");
+        assert!(matches!(
+            info.validate_setpoint(Channel::CH3, 1.0, 1.0),
+            Err(Error::UnsupportedChannel(Channel::CH3))
+        ));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{Channel, Error, Keithley2230, Meas, PowerSequence, Result, State, Transport};
+use std::time::{Duration, Instant};
+
+/// Declarative bench setup: what to program, in which order to enable it and
+/// when to take measurements.
+///
+/// ```
+/// # use keithley_2230_series::*;
+/// let profile = Profile::from_toml_str(
+///     r#"
+/// name = "board-A"
+/// enable_order = ["CH3", "CH1"]
+/// enable_delay_ms = 10
+///
+/// [[channels]]
+/// channel = "CH1"
+/// voltage = 5.0
+/// current = 1.0
+/// ovp = 5.5
+///
+/// [[channels]]
+/// channel = "CH3"
+/// voltage = 1.2
+/// current = 2.0
+///
+/// [[measurements]]
+/// label = "idle"
+/// delay_ms = 500
+/// "#,
+/// )?;
+/// let result = profile.run(&mut Keithley2230::simulated(Model::K2230_30_1))?;
+/// assert_eq!(result.measurements[0].label, "idle");
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub name: String,
+    pub channels: Vec<ProfileChannel>,
+    /// Channels to switch on, in order; defaults to the order of `channels`.
+    #[serde(default)]
+    pub enable_order: Vec<Channel>,
+    /// Pause between switching consecutive channels on or off.
+    #[serde(default)]
+    pub enable_delay_ms: u64,
+    #[serde(default)]
+    pub measurements: Vec<MeasurementPoint>,
+    /// Switch everything off again (in reverse order) once measurements are done.
+    #[serde(default = "default_power_down")]
+    pub power_down: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProfileChannel {
+    pub channel: Channel,
+    pub voltage: f32,
+    pub current: f32,
+    /// Over-voltage protection level; OVP is left untouched when absent.
+    #[serde(default)]
+    pub ovp: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MeasurementPoint {
+    pub label: String,
+    /// Wait before this measurement, counted from the previous one.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProfileResult {
+    pub name: String,
+    pub measurements: Vec<MeasurementResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MeasurementResult {
+    pub label: String,
+    /// Time since the last channel was switched on.
+    pub elapsed: Duration,
+    pub meas: Meas,
+}
+
+fn default_power_down() -> bool {
+    true
+}
+
+impl Profile {
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        toml::from_str(s).map_err(|e| Error::Profile(e.to_string()))
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        serde_json::from_str(s).map_err(|e| Error::Profile(e.to_string()))
+    }
+
+    /// Loads a `.toml` or `.json` profile, chosen by file extension.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&contents),
+            Some("json") => Self::from_json_str(&contents),
+            _ => Err(Error::Profile(format!(
+                "{}: expected a .toml or .json file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn enable_order(&self) -> Vec<Channel> {
+        if self.enable_order.is_empty() {
+            self.channels.iter().map(|c| c.channel).collect()
+        } else {
+            self.enable_order.clone()
+        }
+    }
+
+    /// The enable order as a [`PowerSequence`], with `enable_delay_ms` between channels.
+    pub fn power_sequence(&self) -> PowerSequence {
+        let delay = Duration::from_millis(self.enable_delay_ms);
+        let order = self.enable_order();
+        order
+            .iter()
+            .enumerate()
+            .fold(PowerSequence::new(), |seq, (n, &ch)| {
+                let last = n + 1 == order.len();
+                seq.step(ch, if last { Duration::ZERO } else { delay })
+            })
+    }
+
+    /// Programs, enables and measures the supply as described by the profile.
+    ///
+    /// If anything fails once outputs may be live, the outputs are switched off
+    /// before the error is returned.
+    pub fn run<T: Transport>(&self, k: &mut Keithley2230<T>) -> Result<ProfileResult> {
+        for c in &self.channels {
+            k.validate_setpoint(c.channel, c.voltage, c.current)?;
+        }
+        for ch in self.enable_order() {
+            if !self.channels.iter().any(|c| c.channel == ch) {
+                return Err(Error::Profile(format!(
+                    "{} is in enable_order but has no setpoint",
+                    ch
+                )));
+            }
+        }
+
+        match self.execute(k) {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                let _ = k.enable_output(State::OFF);
+                Err(e)
+            }
+        }
+    }
+
+    fn execute<T: Transport>(&self, k: &mut Keithley2230<T>) -> Result<ProfileResult> {
+        let sequence = self.power_sequence();
+
+        for ch in k.model_info().channels.clone() {
+            k.enable_channel(ch, State::OFF)?;
+        }
+        for c in &self.channels {
+            if let Some(level) = c.ovp {
+                k.set_ovp(c.channel, level)?;
+                k.enable_ovp(c.channel, State::ON)?;
+            }
+            k.set_channel(c.channel, c.voltage, c.current)?;
+        }
+
+        k.power_up(&sequence)?;
+        let enabled_at = Instant::now();
+
+        let mut measurements = Vec::new();
+        for point in &self.measurements {
+            std::thread::sleep(Duration::from_millis(point.delay_ms));
+            measurements.push(MeasurementResult {
+                label: point.label.clone(),
+                elapsed: enabled_at.elapsed(),
+                meas: k.read_all()?,
+            });
+        }
+
+        if self.power_down {
+            k.power_down(&sequence)?;
+        }
+
+        Ok(ProfileResult {
+            name: self.name.clone(),
+            measurements,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    const TOML: &str = r#"
+name = "board-B"
+enable_order = ["CH3", "CH1"]
+enable_delay_ms = 5
+power_down = false
+
+[[channels]]
+channel = "CH1"
+voltage = 5.0
+current = 1.0
+ovp = 5.5
+
+[[channels]]
+channel = "CH3"
+voltage = 1.2
+current = 2.0
+
+[[measurements]]
+label = "idle"
+delay_ms = 20
+"#;
+
+    const JSON: &str = r#"{
+        "name": "board-B",
+        "enable_order": ["CH3", "CH1"],
+        "enable_delay_ms": 5,
+        "power_down": false,
+        "channels": [
+            {"channel": "CH1", "voltage": 5.0, "current": 1.0, "ovp": 5.5},
+            {"channel": "CH3", "voltage": 1.2, "current": 2.0}
+        ],
+        "measurements": [{"label": "idle", "delay_ms": 20}]
+    }"#;
+
+    #[test]
+    fn toml_and_json_agree() {
+        let profile = Profile::from_toml_str(TOML).unwrap();
+        assert_eq!(Profile::from_json_str(JSON).unwrap(), profile);
+        assert_eq!(profile.channels[1].ovp, None);
+        assert_eq!(profile.enable_order(), [Channel::CH3, Channel::CH1]);
+
+        let minimal = Profile::from_toml_str("channels = []").unwrap();
+        assert!(minimal.power_down);
+        assert!(minimal.measurements.is_empty());
+
+        assert!(matches!(
+            Profile::from_toml_str("[[channels]]\nchannel = \"CH4\""),
+            Err(Error::Profile(_))
+        ));
+        assert!(matches!(
+            Profile::from_json_str("{"),
+            Err(Error::Profile(_))
+        ));
+    }
+
+    #[test]
+    fn load_picks_format_by_extension() {
+        let dir = std::env::temp_dir().join(format!("k2230-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let toml = dir.join("bench.toml");
+        let json = dir.join("bench.json");
+        let txt = dir.join("bench.txt");
+        std::fs::write(&toml, TOML).unwrap();
+        std::fs::write(&json, JSON).unwrap();
+        std::fs::write(&txt, TOML).unwrap();
+
+        assert_eq!(Profile::load(&toml).unwrap(), Profile::load(&json).unwrap());
+        assert!(matches!(Profile::load(&txt), Err(Error::Profile(_))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn run_on_simulator() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.set_error_checking(true);
+        let result = Profile::from_toml_str(TOML).unwrap().run(&mut k).unwrap();
+
+        assert_eq!(result.name, "board-B");
+        let [idle] = &result.measurements[..] else {
+            panic!("{:?}", result.measurements);
+        };
+        assert_eq!(idle.label, "idle");
+        assert!(idle.elapsed >= Duration::from_millis(20));
+        // Default 10 ohm load on every channel.
+        assert!((idle.meas.ch1.i - 0.5).abs() < 1e-4, "{:?}", idle.meas);
+        assert!((idle.meas.ch3.i - 0.12).abs() < 1e-4, "{:?}", idle.meas);
+        assert_eq!(idle.meas.ch2.v, 0.0);
+
+        // power_down = false leaves the channels live.
+        assert_eq!(k.output_state().unwrap(), State::ON);
+        assert_eq!(k.get_ovp(Channel::CH1).unwrap(), 5.5);
+        assert_eq!(k.ovp_state(Channel::CH1).unwrap(), State::ON);
+    }
+
+    #[test]
+    fn enable_order_needs_a_setpoint() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        let mut profile = Profile::from_toml_str(TOML).unwrap();
+        profile.enable_order.push(Channel::CH2);
+
+        assert!(matches!(
+            profile.run(&mut k),
+            Err(Error::Profile(message)) if message.contains("CH2")
+        ));
+        assert_eq!(k.output_state().unwrap(), State::OFF);
+        assert_eq!(k.get_setpoint(Channel::CH1).unwrap().voltage, 0.0);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{check_range, Channel, Keithley2230, Result, State, Transport};
+
+/// Highest OVP level accepted, relative to the channel's rated voltage.
+const OVP_HEADROOM: f32 = 1.1;
+
+impl<T: Transport> Keithley2230<T> {
+    /// Sets the over-voltage protection level of `ch` in volts.
+    pub fn set_ovp(&mut self, ch: Channel, level: f32) -> Result<()> {
+        self.validate_ovp(ch, level)?;
+        self.with_channel(ch, |k| k.command(&format!("VOLT:PROT {}", level)))
+    }
+
+    /// Checks an OVP level for `ch` without sending anything.
+    pub(crate) fn validate_ovp(&self, ch: Channel, level: f32) -> Result<()> {
+        let limits = self.channel_limits(ch)?;
+        check_range(
+            &format!("{} OVP level", ch),
+            level,
+            0.0,
+            limits.max_voltage * OVP_HEADROOM,
+        )
+    }
+
+    pub fn get_ovp(&mut self, ch: Channel) -> Result<f32> {
+        self.with_channel(ch, |k| k.query_number("VOLT:PROT?"))
+    }
+
+    pub fn enable_ovp(&mut self, ch: Channel, state: State) -> Result<()> {
+        self.with_channel(ch, |k| k.command(&format!("VOLT:PROT:STAT {}", state)))
+    }
+
+    pub fn ovp_state(&mut self, ch: Channel) -> Result<State> {
+        self.with_channel(ch, |k| k.query_state("VOLT:PROT:STAT?"))
+    }
+
+    /// Returns `true` if OVP on `ch` has tripped and switched the channel off.
+    pub fn ovp_tripped(&mut self, ch: Channel) -> Result<bool> {
+        let state = self.with_channel(ch, |k| k.query_state("VOLT:PROT:TRIP?"))?;
+        Ok(state == State::ON)
+    }
+
+    /// Clears a tripped OVP on `ch`; the channel output stays off until re-enabled.
+    pub fn clear_ovp(&mut self, ch: Channel) -> Result<()> {
+        self.with_channel(ch, |k| k.command("VOLT:PROT:CLE"))
+    }
+}
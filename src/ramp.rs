@@ -0,0 +1,266 @@
+use crate::{channel_value, check_range, Channel, Error, Keithley2230, Result, Transport};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Shared flag for stopping a running [`Ramp`] from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Linear voltage ramp from `from` to `to` over `duration`, in `steps` equal steps.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ramp {
+    pub from: f32,
+    pub to: f32,
+    pub duration: Duration,
+    pub steps: u32,
+    /// Abort when the channel draws more than this many amps.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub abort_above: Option<f32>,
+    /// Runtime only; never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cancel: Option<CancelToken>,
+}
+
+impl Ramp {
+    /// Smallest step [`Ramp::step_size`] accepts: the 1 mV setpoint resolution.
+    pub const MIN_STEP: f32 = 0.001;
+
+    pub fn new(from: f32, to: f32, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            steps: 10,
+            abort_above: None,
+            cancel: None,
+        }
+    }
+
+    pub fn steps(mut self, steps: u32) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+
+    /// Picks the step count so that no step is larger than `volts`, which must be
+    /// at least [`Ramp::MIN_STEP`].
+    pub fn step_size(mut self, volts: f32) -> Result<Self> {
+        check_range("ramp step size (V)", volts, Self::MIN_STEP, f32::MAX)?;
+        let steps = ((self.to - self.from).abs() / volts).ceil();
+        self.steps = if steps.is_finite() {
+            steps.max(1.0) as u32
+        } else {
+            1
+        };
+        Ok(self)
+    }
+
+    pub fn abort_above_current(mut self, amps: f32) -> Self {
+        self.abort_above = Some(amps);
+        self
+    }
+
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Voltage programmed at step `n` of `0..=steps`.
+    pub fn voltage_at(&self, n: u32) -> f32 {
+        let steps = self.steps.max(1);
+        self.from + (self.to - self.from) * n.min(steps) as f32 / steps as f32
+    }
+}
+
+impl<T: Transport> Keithley2230<T> {
+    /// Steps the voltage of `ch` from `from` to `to` over `duration`, leaving the
+    /// current limit as programmed.
+    pub fn ramp_voltage(
+        &mut self,
+        ch: Channel,
+        from: f32,
+        to: f32,
+        duration: Duration,
+        steps: u32,
+    ) -> Result<()> {
+        self.ramp(ch, &Ramp::new(from, to, duration).steps(steps))
+    }
+
+    /// Runs `ramp` on `ch`. On cancelation or over-current the voltage is left
+    /// at the last programmed step.
+    pub fn ramp(&mut self, ch: Channel, ramp: &Ramp) -> Result<()> {
+        let limits = self.channel_limits(ch)?;
+        for v in [ramp.from, ramp.to] {
+            check_range(&format!("{} voltage", ch), v, 0.0, limits.max_voltage)?;
+        }
+        let interval = ramp.duration / ramp.steps.max(1);
+
+        self.with_channel(ch, |k| {
+            let start = Instant::now();
+            for n in 0..=ramp.steps.max(1) {
+                if ramp.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                    return Err(Error::Cancelled());
+                }
+                k.command(&format!("VOLT {}", ramp.voltage_at(n)))?;
+                if let Some(limit) = ramp.abort_above {
+                    let measured = channel_value(k.read_i()?, ch);
+                    if measured > limit {
+                        return Err(Error::CurrentLimitExceeded {
+                            channel: ch,
+                            measured,
+                            limit,
+                        });
+                    }
+                }
+                // Sleep to the step's deadline so command latency doesn't stretch the ramp.
+                let deadline = interval * (n + 1);
+                if let Some(remaining) = deadline.checked_sub(start.elapsed()) {
+                    if n < ramp.steps {
+                        std::thread::sleep(remaining);
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Model, Simulator, State};
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn supply() -> Keithley2230<Simulator> {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.set_error_checking(true);
+        k
+    }
+
+    fn voltage(k: &mut Keithley2230<Simulator>, ch: Channel) -> f32 {
+        k.get_setpoint(ch).unwrap().voltage
+    }
+
+    #[test]
+    fn step_size_picks_step_count() {
+        let ramp = Ramp::new(0.0, 5.0, ms(10));
+        assert_eq!(ramp.clone().step_size(0.5).unwrap().steps, 10);
+        assert_eq!(ramp.clone().step_size(0.3).unwrap().steps, 17);
+        assert_eq!(ramp.clone().step_size(10.0).unwrap().steps, 1);
+        assert_eq!(Ramp::new(5.0, 0.0, ms(10)).step_size(1.0).unwrap().steps, 5);
+
+        for volts in [0.0, -1.0, f32::NAN, f32::INFINITY, 1e-9] {
+            assert!(
+                matches!(ramp.clone().step_size(volts), Err(Error::OutOfRange { .. })),
+                "step size {} accepted",
+                volts
+            );
+        }
+    }
+
+    #[test]
+    fn ramp_reaches_target() {
+        let mut k = supply();
+        k.select_channel(Channel::CH2).unwrap();
+        let ramp = Ramp::new(0.0, 5.0, ms(50)).steps(5);
+        assert_eq!(ramp.voltage_at(2), 2.0);
+
+        let start = Instant::now();
+        k.ramp(Channel::CH1, &ramp).unwrap();
+        assert!(start.elapsed() >= ms(40), "{:?}", start.elapsed());
+        assert_eq!(voltage(&mut k, Channel::CH1), 5.0);
+        assert_eq!(k.get_channel().unwrap(), Channel::CH2);
+
+        let mut k = Keithley2230::simulated(Model::K2220_30_1);
+        assert!(matches!(
+            k.ramp(Channel::CH3, &ramp),
+            Err(Error::UnsupportedChannel(Channel::CH3))
+        ));
+        assert!(matches!(
+            k.ramp(Channel::CH1, &Ramp::new(0.0, 31.0, ms(10))),
+            Err(Error::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn cancel_stops_ramp() {
+        let mut k = supply();
+        let token = CancelToken::new();
+        let ramp = Ramp::new(0.0, 10.0, Duration::from_secs(2))
+            .steps(20)
+            .cancel_token(token.clone());
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(ms(250));
+            token.cancel();
+        });
+        let start = Instant::now();
+        assert!(matches!(
+            k.ramp(Channel::CH1, &ramp),
+            Err(Error::Cancelled())
+        ));
+        canceller.join().unwrap();
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "{:?}",
+            start.elapsed()
+        );
+
+        // Left at the last programmed step.
+        let v = voltage(&mut k, Channel::CH1);
+        assert!(v > 0.0 && v < 10.0, "{}", v);
+        assert_eq!(k.get_channel().unwrap(), Channel::CH1);
+
+        // An already cancelled token sends nothing.
+        k.set_channel(Channel::CH2, 1.0, 1.0).unwrap();
+        assert!(matches!(
+            k.ramp(Channel::CH2, &ramp),
+            Err(Error::Cancelled())
+        ));
+        assert_eq!(voltage(&mut k, Channel::CH2), 1.0);
+    }
+
+    #[test]
+    fn over_current_aborts() {
+        let mut k = supply();
+        k.set_channel(Channel::CH1, 0.0, 3.0).unwrap();
+        k.enable_channel(Channel::CH1, State::ON).unwrap();
+        k.enable_output(State::ON).unwrap();
+
+        // Default 10 ohm load: 1 V steps draw 0.1 A more each.
+        let ramp = Ramp::new(0.0, 20.0, Duration::ZERO)
+            .step_size(1.0)
+            .unwrap()
+            .abort_above_current(0.95);
+        match k.ramp(Channel::CH1, &ramp) {
+            Err(Error::CurrentLimitExceeded {
+                channel,
+                measured,
+                limit,
+            }) => {
+                assert_eq!((channel, limit), (Channel::CH1, 0.95));
+                assert!((measured - 1.0).abs() < 1e-4, "{}", measured);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(voltage(&mut k, Channel::CH1), 10.0);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{Error, Result, Transport};
+use std::time::Duration;
+
+/// Transports that can re-open their link after it dropped.
+pub trait Reconnect: Transport {
+    fn reconnect(&mut self) -> Result<()>;
+}
+
+/// How often and how patiently [`ResilientTransport`] retries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `attempt` (starting at 0).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = f64::from(self.multiplier.max(1.0)).powi(attempt as i32);
+        let backoff = self.initial_backoff.as_secs_f64() * factor;
+        Duration::try_from_secs_f64(backoff)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+/// Commands whose effect changes when sent twice, so they are never replayed
+/// after a reconnect.
+const NON_IDEMPOTENT: &[&str] = &["SYST:ERR?", "LIST:STAT", "OUTP:TIM:STAT", "*TRG"];
+
+/// Whether `cmd` can safely be sent again when it is unknown if the first
+/// attempt reached the instrument.
+pub fn is_idempotent(cmd: &str) -> bool {
+    cmd.split(';').all(|unit| {
+        let header = unit
+            .trim()
+            .trim_start_matches(':')
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_ascii_uppercase();
+        !NON_IDEMPOTENT.contains(&header.as_str())
+    })
+}
+
+/// Wraps a [`Reconnect`] transport and transparently re-opens it on transient
+/// errors, restoring the last channel selection.
+///
+/// Idempotent commands and queries are replayed after a reconnect; anything
+/// else fails with [`Error::NotReplayed`] once the link is back, leaving the
+/// decision to the caller.
+///
+/// ```no_run
+/// # use keithley_2230_series::*;
+/// let tcp = TcpTransport::connect("192.168.0.10:5025", TcpTransport::DEFAULT_TIMEOUT)?;
+/// let mut k = Keithley2230::with_transport(ResilientTransport::new(tcp, RetryPolicy::default()))?;
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ResilientTransport<T: Reconnect> {
+    inner: T,
+    policy: RetryPolicy,
+    /// Last `INST` command written, re-sent after reconnecting.
+    selection: Option<String>,
+    /// Query whose response is still to be read.
+    pending_query: Option<String>,
+}
+
+impl<T: Reconnect> ResilientTransport<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            selection: None,
+            pending_query: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    fn track(&mut self, cmd: &str) {
+        let header = cmd.trim().trim_start_matches(':').to_ascii_uppercase();
+        if header.starts_with("INST ") && !cmd.contains(';') {
+            self.selection = Some(cmd.trim().to_string());
+        }
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        self.inner.reconnect()?;
+        if let Some(selection) = self.selection.clone() {
+            self.inner.write(&selection)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `op`, reconnecting and replaying it when it is safe to do so.
+    ///
+    /// At most `max_retries` re-opens are attempted per call, with the backoff
+    /// growing across all of them.
+    fn retry<R>(&mut self, cmd: &str, mut op: impl FnMut(&mut T) -> Result<R>) -> Result<R> {
+        let mut error = match op(&mut self.inner) {
+            Ok(r) => return Ok(r),
+            Err(e) if e.is_transient() => e,
+            Err(e) => return Err(e),
+        };
+        for attempt in 0..self.policy.max_retries {
+            std::thread::sleep(self.policy.backoff(attempt));
+            // The link may take a while to come back, so any re-open failure is retried.
+            if let Err(e) = self.reopen() {
+                error = e;
+                continue;
+            }
+            if !is_idempotent(cmd) {
+                return Err(Error::NotReplayed(cmd.to_string()));
+            }
+            match op(&mut self.inner) {
+                Ok(r) => return Ok(r),
+                Err(e) if e.is_transient() => error = e,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(error)
+    }
+}
+
+impl<T: Reconnect> Transport for ResilientTransport<T> {
+    fn write(&mut self, command: &str) -> Result<()> {
+        self.pending_query = command.contains('?').then(|| command.to_string());
+        self.retry(command, |t| t.write(command))?;
+        self.track(command);
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<String> {
+        let Some(query) = self.pending_query.take() else {
+            return self.inner.read();
+        };
+        // A lost response can only be recovered by asking again.
+        let mut first = true;
+        self.retry(&query, |t| {
+            if !std::mem::take(&mut first) {
+                t.write(&query)?;
+            }
+            t.read()
+        })
+    }
+
+    fn query(&mut self, command: &str) -> Result<String> {
+        self.pending_query = None;
+        let response = self.retry(command, |t| t.query(command))?;
+        self.track(command);
+        Ok(response)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.pending_query = None;
+        self.retry("", |t| t.clear())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Model, Simulator};
+    use std::io::ErrorKind;
+
+    /// Simulator whose link can be made to drop on the next writes, reads or re-opens.
+    struct Flaky {
+        sim: Simulator,
+        sent: Vec<String>,
+        drop_writes: u32,
+        drop_reads: u32,
+        refuse_reconnects: u32,
+        reconnects: u32,
+    }
+
+    impl Flaky {
+        fn new() -> Self {
+            Self {
+                sim: Simulator::new(Model::K2230_30_1),
+                sent: Vec::new(),
+                drop_writes: 0,
+                drop_reads: 0,
+                refuse_reconnects: 0,
+                reconnects: 0,
+            }
+        }
+    }
+
+    fn dropped() -> Error {
+        Error::Io(ErrorKind::ConnectionReset.into())
+    }
+
+    impl Transport for Flaky {
+        fn write(&mut self, command: &str) -> Result<()> {
+            if self.drop_writes > 0 {
+                self.drop_writes -= 1;
+                return Err(dropped());
+            }
+            self.sent.push(command.to_string());
+            self.sim.write(command)
+        }
+
+        fn read(&mut self) -> Result<String> {
+            if self.drop_reads > 0 {
+                self.drop_reads -= 1;
+                self.sim.read()?;
+                return Err(dropped());
+            }
+            self.sim.read()
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.sim.clear()
+        }
+    }
+
+    impl Reconnect for Flaky {
+        fn reconnect(&mut self) -> Result<()> {
+            self.reconnects += 1;
+            if self.refuse_reconnects > 0 {
+                self.refuse_reconnects -= 1;
+                return Err(Error::Io(ErrorKind::ConnectionRefused.into()));
+            }
+            self.sim.reconnect()
+        }
+    }
+
+    fn resilient() -> ResilientTransport<Flaky> {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            ..RetryPolicy::default()
+        };
+        ResilientTransport::new(Flaky::new(), policy)
+    }
+
+    #[test]
+    fn idempotent_command_is_replayed_after_restoring_channel() {
+        let mut t = resilient();
+        t.write("INST CH2").unwrap();
+        t.inner.drop_writes = 1;
+        t.write("VOLT 3").unwrap();
+
+        assert_eq!(t.get_ref().reconnects, 1);
+        assert_eq!(t.get_ref().sent, ["INST CH2", "INST CH2", "VOLT 3"]);
+        assert_eq!(t.query("INST?").unwrap(), "CH2");
+    }
+
+    #[test]
+    fn non_idempotent_command_is_not_replayed() {
+        let mut t = resilient();
+        t.inner.drop_writes = 1;
+        let result = t.write("LIST:STAT ON");
+
+        assert!(matches!(result, Err(Error::NotReplayed(cmd)) if cmd == "LIST:STAT ON"));
+        assert_eq!(t.get_ref().reconnects, 1);
+        assert!(t.get_ref().sent.is_empty());
+    }
+
+    #[test]
+    fn lost_response_is_asked_for_again() {
+        let mut t = resilient();
+        t.write("INST CH3").unwrap();
+        t.inner.drop_reads = 1;
+
+        assert_eq!(t.query("INST?").unwrap(), "CH3");
+        t.write("INST?").unwrap();
+        t.inner.drop_reads = 1;
+        assert_eq!(t.read().unwrap(), "CH3");
+        assert_eq!(t.get_ref().reconnects, 2);
+    }
+
+    #[test]
+    fn reopen_attempts_are_bounded_per_call() {
+        let mut t = resilient();
+        t.inner.drop_writes = 1;
+        t.inner.refuse_reconnects = u32::MAX;
+
+        assert!(
+            matches!(t.write("VOLT 1"), Err(Error::Io(e)) if e.kind() == ErrorKind::ConnectionRefused)
+        );
+        assert_eq!(t.get_ref().reconnects, RetryPolicy::default().max_retries);
+    }
+
+    #[test]
+    fn link_that_comes_back_late_is_recovered() {
+        let mut t = resilient();
+        t.inner.drop_writes = 1;
+        t.inner.refuse_reconnects = 2;
+
+        t.write("VOLT 1").unwrap();
+        assert_eq!(t.get_ref().reconnects, 3);
+    }
+
+    #[test]
+    fn instrument_errors_are_not_retried() {
+        let mut t = resilient();
+        let mut k = crate::Keithley2230::with_transport(&mut t).unwrap();
+        k.set_error_checking(true);
+        k.inner.write("VOLT 99").unwrap();
+        assert!(!k.read_error_queue().unwrap().is_empty());
+        assert_eq!(t.get_ref().reconnects, 0);
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(10), policy.max_backoff);
+    }
+
+    #[test]
+    fn idempotency_rules() {
+        assert!(is_idempotent("APPL CH1, 5, 1"));
+        assert!(is_idempotent("FETC:VOLT? ALL;:FETC:CURR? ALL"));
+        assert!(!is_idempotent("SYST:ERR?"));
+        assert!(!is_idempotent(":list:stat on"));
+        assert!(!is_idempotent("INST CH1;*TRG"));
+    }
+}
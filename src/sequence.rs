@@ -0,0 +1,244 @@
+use crate::{channel_value, Channel, Error, Keithley2230, Result, State, Transport};
+use std::time::{Duration, Instant};
+
+/// How often a [`VoltageGate`] polls the measured voltage.
+const GATE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Blocks a sequence until the channel's measured voltage is within
+/// `tolerance` of `target`, failing after `timeout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VoltageGate {
+    pub target: f32,
+    pub tolerance: f32,
+    pub timeout: Duration,
+}
+
+/// Switch `channel`, wait for the optional gate, then pause for `delay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerStep {
+    pub channel: Channel,
+    pub delay: Duration,
+    pub gate: Option<VoltageGate>,
+}
+
+/// Ordered power-up and power-down of individual channels.
+///
+/// ```no_run
+/// # use keithley_2230_series::*;
+/// # use std::time::Duration;
+/// // Core rail before I/O, 10 ms apart; shutdown runs in reverse.
+/// let seq = PowerSequence::new()
+///     .step(Channel::CH3, Duration::from_millis(10))
+///     .step(Channel::CH1, Duration::ZERO);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerSequence {
+    pub up: Vec<PowerStep>,
+    /// Explicit shutdown order; when empty, `up` is replayed in reverse.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub down: Vec<PowerStep>,
+}
+
+impl PowerSequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, channel: Channel, delay: Duration) -> Self {
+        self.up.push(PowerStep {
+            channel,
+            delay,
+            gate: None,
+        });
+        self
+    }
+
+    pub fn gated_step(mut self, channel: Channel, delay: Duration, gate: VoltageGate) -> Self {
+        self.up.push(PowerStep {
+            channel,
+            delay,
+            gate: Some(gate),
+        });
+        self
+    }
+
+    pub fn down_step(
+        mut self,
+        channel: Channel,
+        delay: Duration,
+        gate: Option<VoltageGate>,
+    ) -> Self {
+        self.down.push(PowerStep {
+            channel,
+            delay,
+            gate,
+        });
+        self
+    }
+
+    /// Shutdown steps, reversing `up` when no explicit order was given.
+    ///
+    /// A reversed step waits for the delay that preceded it on the way up and
+    /// carries no gate.
+    pub fn down_steps(&self) -> Vec<PowerStep> {
+        if !self.down.is_empty() {
+            return self.down.clone();
+        }
+        let mut steps = self
+            .up
+            .iter()
+            .rev()
+            .map(|s| PowerStep {
+                channel: s.channel,
+                delay: Duration::ZERO,
+                gate: None,
+            })
+            .collect::<Vec<PowerStep>>();
+        for (n, step) in steps.iter_mut().enumerate() {
+            if let Some(prev) = self.up.iter().rev().nth(n + 1) {
+                step.delay = prev.delay;
+            }
+        }
+        steps
+    }
+}
+
+impl<T: Transport> Keithley2230<T> {
+    /// Switches every channel off, enables the main output, then switches the
+    /// channels of `seq` on in order.
+    ///
+    /// If a step fails the main output is switched off before returning.
+    pub fn power_up(&mut self, seq: &PowerSequence) -> Result<()> {
+        for step in &seq.up {
+            self.channel_limits(step.channel)?;
+        }
+        let result = self.run_power_up(seq);
+        if result.is_err() {
+            let _ = self.enable_output(State::OFF);
+        }
+        result
+    }
+
+    fn run_power_up(&mut self, seq: &PowerSequence) -> Result<()> {
+        for ch in self.info.channels.clone() {
+            self.enable_channel(ch, State::OFF)?;
+        }
+        self.enable_output(State::ON)?;
+        for step in &seq.up {
+            self.run_step(step, State::ON)?;
+        }
+        Ok(())
+    }
+
+    /// Switches the channels off in shutdown order, then disables the main output.
+    pub fn power_down(&mut self, seq: &PowerSequence) -> Result<()> {
+        for step in seq.down_steps() {
+            self.run_step(&step, State::OFF)?;
+        }
+        self.enable_output(State::OFF)
+    }
+
+    fn run_step(&mut self, step: &PowerStep, state: State) -> Result<()> {
+        self.enable_channel(step.channel, state)?;
+        if let Some(gate) = &step.gate {
+            self.wait_for_voltage(step.channel, gate)?;
+        }
+        std::thread::sleep(step.delay);
+        Ok(())
+    }
+
+    fn wait_for_voltage(&mut self, ch: Channel, gate: &VoltageGate) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            let measured = channel_value(self.read_v()?, ch);
+            if (measured - gate.target).abs() <= gate.tolerance {
+                return Ok(());
+            }
+            if start.elapsed() >= gate.timeout {
+                return Err(Error::GateTimeout {
+                    channel: ch,
+                    target: gate.target,
+                    measured,
+                });
+            }
+            std::thread::sleep(GATE_POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn down_steps_reverse_up() {
+        let seq = PowerSequence::new()
+            .step(Channel::CH3, ms(1))
+            .gated_step(
+                Channel::CH1,
+                ms(2),
+                VoltageGate {
+                    target: 5.0,
+                    tolerance: 0.1,
+                    timeout: ms(100),
+                },
+            )
+            .step(Channel::CH2, ms(3));
+        let down = seq.down_steps();
+        let order = down.iter().map(|s| s.channel).collect::<Vec<_>>();
+        assert_eq!(order, [Channel::CH2, Channel::CH1, Channel::CH3]);
+        let delays = down.iter().map(|s| s.delay).collect::<Vec<_>>();
+        assert_eq!(delays, [ms(2), ms(1), Duration::ZERO]);
+        assert!(down.iter().all(|s| s.gate.is_none()));
+
+        assert!(PowerSequence::new().down_steps().is_empty());
+    }
+
+    #[test]
+    fn explicit_down_order_is_kept() {
+        let seq = PowerSequence::new()
+            .step(Channel::CH1, ms(1))
+            .step(Channel::CH2, ms(1))
+            .down_step(Channel::CH1, ms(4), None);
+        assert_eq!(seq.down_steps(), seq.down);
+    }
+
+    #[test]
+    fn power_up_and_down_on_simulator() {
+        let mut k = Keithley2230::simulated(Model::K2230_30_1);
+        k.enable_channel(Channel::CH3, State::ON).unwrap();
+        let seq = PowerSequence::new()
+            .step(Channel::CH1, Duration::ZERO)
+            .step(Channel::CH2, Duration::ZERO);
+
+        k.power_up(&seq).unwrap();
+        assert_eq!(k.output_state().unwrap(), State::ON);
+        assert_eq!(k.channel_state(Channel::CH1).unwrap(), State::ON);
+        assert_eq!(k.channel_state(Channel::CH2).unwrap(), State::ON);
+        assert_eq!(k.channel_state(Channel::CH3).unwrap(), State::OFF);
+
+        k.power_down(&seq).unwrap();
+        assert_eq!(k.output_state().unwrap(), State::OFF);
+        assert_eq!(k.channel_state(Channel::CH1).unwrap(), State::OFF);
+        assert_eq!(k.channel_state(Channel::CH2).unwrap(), State::OFF);
+    }
+
+    #[test]
+    fn power_up_checks_channels_before_switching() {
+        let mut k = Keithley2230::simulated(Model::K2220_30_1);
+        let seq = PowerSequence::new().step(Channel::CH3, Duration::ZERO);
+        assert!(matches!(
+            k.power_up(&seq),
+            Err(Error::UnsupportedChannel(Channel::CH3))
+        ));
+        assert_eq!(k.output_state().unwrap(), State::OFF);
+    }
+}
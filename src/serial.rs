@@ -0,0 +1,213 @@
+use crate::transport::map_io;
+use crate::{Error, Reconnect, Result, Transport};
+use std::io::{ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+
+pub use serialport::{DataBits, FlowControl, Parity, StopBits};
+
+/// Line settings for the USB virtual COM / RS-232 interface.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SerialConfig {
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+    pub read_terminator: String,
+    pub write_terminator: String,
+    pub timeout: Duration,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: 9600,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            read_terminator: "\n".to_string(),
+            write_terminator: "\n".to_string(),
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+pub struct SerialTransport {
+    port: Box<dyn serialport::SerialPort>,
+    config: SerialConfig,
+    pending: Vec<u8>,
+}
+
+impl std::fmt::Debug for SerialTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SerialTransport")
+            .field("port", &self.port.name())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl SerialTransport {
+    /// Opens the device at `path`, e.g. `/dev/ttyACM0` or `COM3`.
+    pub fn open(path: &str, config: SerialConfig) -> Result<Self> {
+        let port = serialport::new(path, config.baud_rate)
+            .data_bits(config.data_bits)
+            .parity(config.parity)
+            .stop_bits(config.stop_bits)
+            .flow_control(config.flow_control)
+            .timeout(config.timeout)
+            .open()?;
+        Self::from_port(port, config)
+    }
+
+    /// Wraps an already opened port, such as one end of a pseudo-terminal pair.
+    pub fn from_port(
+        mut port: Box<dyn serialport::SerialPort>,
+        config: SerialConfig,
+    ) -> Result<Self> {
+        if config.read_terminator.is_empty() {
+            return Err(Error::Io(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "read terminator must not be empty",
+            )));
+        }
+        port.set_baud_rate(config.baud_rate)?;
+        port.set_data_bits(config.data_bits)?;
+        port.set_parity(config.parity)?;
+        port.set_stop_bits(config.stop_bits)?;
+        port.set_flow_control(config.flow_control)?;
+        port.set_timeout(config.timeout)?;
+        Ok(Self {
+            port,
+            config,
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    pub fn config(&self) -> &SerialConfig {
+        &self.config
+    }
+
+    fn take_line(&mut self) -> Option<String> {
+        let terminator = self.config.read_terminator.as_bytes();
+        let end = self
+            .pending
+            .windows(terminator.len())
+            .position(|w| w == terminator)?;
+        let line = self
+            .pending
+            .drain(..end + terminator.len())
+            .collect::<Vec<u8>>();
+        let line = String::from_utf8_lossy(&line[..end]);
+        Some(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+impl Transport for SerialTransport {
+    fn write(&mut self, command: &str) -> Result<()> {
+        let line = format!("{}{}", command, self.config.write_terminator);
+        self.port.write_all(line.as_bytes()).map_err(map_io)?;
+        self.port.flush().map_err(map_io)?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<String> {
+        let deadline = Instant::now() + self.config.timeout;
+        let mut chunk = [0u8; 256];
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(line);
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout());
+            }
+            match self.port.read(&mut chunk) {
+                Ok(0) => return Err(Error::Io(ErrorKind::UnexpectedEof.into())),
+                Ok(n) => self.pending.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(map_io(e)),
+            }
+        }
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.pending.clear();
+        self.port.clear(serialport::ClearBuffer::All)?;
+        Ok(())
+    }
+}
+
+impl Reconnect for SerialTransport {
+    fn reconnect(&mut self) -> Result<()> {
+        let path = self.port.name().ok_or_else(|| {
+            Error::Io(std::io::Error::new(
+                ErrorKind::NotFound,
+                "port has no device path to reopen",
+            ))
+        })?;
+        *self = Self::open(&path, self.config.clone())?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serialport::TTYPort;
+
+    fn pty(config: SerialConfig) -> (TTYPort, SerialTransport) {
+        let (master, slave) = TTYPort::pair().unwrap();
+        let transport = SerialTransport::from_port(Box::new(slave), config).unwrap();
+        (master, transport)
+    }
+
+    fn crlf() -> SerialConfig {
+        SerialConfig {
+            read_terminator: "\r\n".to_string(),
+            write_terminator: "\r\n".to_string(),
+            timeout: Duration::from_millis(100),
+            ..SerialConfig::default()
+        }
+    }
+
+    #[test]
+    fn custom_terminators() {
+        let (mut master, mut t) = pty(crlf());
+        t.write("*IDN?").unwrap();
+        let mut sent = [0u8; 7];
+        master.read_exact(&mut sent).unwrap();
+        assert_eq!(&sent, b"*IDN?\r\n");
+
+        // A bare newline is data when the terminator is CR LF.
+        master.write_all(b"a\nb\r\nc\r\n").unwrap();
+        assert_eq!(t.read().unwrap(), "a\nb");
+        assert_eq!(t.read().unwrap(), "c");
+    }
+
+    #[test]
+    fn line_split_across_reads_survives_timeout() {
+        let (mut master, mut t) = pty(crlf());
+        master.write_all(b"1.0").unwrap();
+        assert!(matches!(t.read(), Err(Error::Timeout())));
+        master.write_all(b"00\r").unwrap();
+        assert!(matches!(t.read(), Err(Error::Timeout())));
+        master.write_all(b"\n").unwrap();
+        assert_eq!(t.read().unwrap(), "1.000");
+    }
+
+    #[test]
+    fn read_times_out_without_data() {
+        let (_master, mut t) = pty(crlf());
+        let start = Instant::now();
+        assert!(matches!(t.read(), Err(Error::Timeout())));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+    }
+}
@@ -0,0 +1,572 @@
+use crate::{Channel, ChannelLimits, Error, Model, Reconnect, Result, Transport};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const SIM_SERIAL: &str = "9000001";
+const SIM_FIRMWARE: &str = "1.16-1.04";
+
+const ERROR_QUEUE_LEN: usize = 16;
+
+/// In-process stand-in for a 2230 that understands the SCPI emitted by this crate.
+///
+/// Each channel drives a resistive load so readings follow the programmed
+/// setpoints, including falling back to constant-current when the load would
+/// draw more than the current limit.
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    model: Model,
+    limits: Vec<ChannelLimits>,
+    channels: [SimChannel; 3],
+    selected: Channel,
+    output: bool,
+    parallel: bool,
+    series: bool,
+    tracking: bool,
+    remote: bool,
+    timer_delay: Duration,
+    timer_enabled: bool,
+    output_since: Option<Instant>,
+    saved_lists: HashMap<u8, SimList>,
+    saved_setups: HashMap<u8, Vec<(f32, f32, f32, bool)>>,
+    responses: VecDeque<String>,
+    errors: VecDeque<(i32, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct SimList {
+    /// (voltage, current, dwell) per step.
+    steps: Vec<(f32, f32, Duration)>,
+    count: u32,
+}
+
+impl Default for SimList {
+    fn default() -> Self {
+        Self {
+            steps: Vec::new(),
+            count: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SimChannel {
+    voltage: f32,
+    current: f32,
+    enabled: bool,
+    load: f32,
+    ovp_level: f32,
+    ovp_enabled: bool,
+    ovp_tripped: bool,
+    list: SimList,
+    list_started: Option<Instant>,
+}
+
+impl Default for SimChannel {
+    fn default() -> Self {
+        Self {
+            voltage: 0.0,
+            current: 0.1,
+            enabled: false,
+            load: 10.0,
+            ovp_level: 0.0,
+            ovp_enabled: false,
+            ovp_tripped: false,
+            list: SimList::default(),
+            list_started: None,
+        }
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new(Model::K2230_30_1)
+    }
+}
+
+impl Simulator {
+    pub fn new(model: Model) -> Self {
+        let limits = model.limits();
+        let mut channels: [SimChannel; 3] = Default::default();
+        for l in &limits {
+            channels[index(l.channel)].ovp_level = l.max_voltage * 1.1;
+        }
+        Self {
+            model,
+            limits,
+            channels,
+            selected: Channel::CH1,
+            output: false,
+            parallel: false,
+            series: false,
+            tracking: false,
+            remote: false,
+            timer_delay: Duration::from_secs(60),
+            timer_enabled: false,
+            output_since: None,
+            saved_lists: HashMap::new(),
+            saved_setups: HashMap::new(),
+            responses: VecDeque::new(),
+            errors: VecDeque::new(),
+        }
+    }
+
+    /// Sets the resistance of the simulated load on `ch` in ohms.
+    pub fn set_load(&mut self, ch: Channel, ohms: f32) {
+        self.channels[index(ch)].load = ohms;
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub fn setpoint(&self, ch: Channel) -> (f32, f32) {
+        let c = &self.channels[index(ch)];
+        (c.voltage, c.current)
+    }
+
+    pub fn channel_enabled(&self, ch: Channel) -> bool {
+        self.channels[index(ch)].enabled
+    }
+
+    pub fn output_enabled(&self) -> bool {
+        self.output
+    }
+
+    pub fn selected_channel(&self) -> Channel {
+        self.selected
+    }
+
+    pub fn is_parallel(&self) -> bool {
+        self.parallel
+    }
+
+    pub fn is_series(&self) -> bool {
+        self.series
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.tracking
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    /// Returns the (voltage, current, power) the simulated load sees on `ch`.
+    pub fn reading(&self, ch: Channel) -> (f32, f32, f32) {
+        let c = &self.channels[index(ch)];
+        if !(self.output && c.enabled) {
+            return (0.0, 0.0, 0.0);
+        }
+        let (v, i) = if c.load <= 0.0 || c.voltage / c.load > c.current {
+            (c.current * c.load.max(0.0), c.current)
+        } else {
+            (c.voltage, c.voltage / c.load)
+        };
+        (v, i, v * i)
+    }
+
+    fn push_error(&mut self, code: i32, message: &str) {
+        if self.errors.len() < ERROR_QUEUE_LEN {
+            self.errors.push_back((code, message.to_string()));
+        }
+    }
+
+    fn execute(&mut self, command: &str) {
+        let command = command.trim().trim_start_matches(':');
+        if command.is_empty() {
+            return;
+        }
+        let (header, args) = match command.split_once(char::is_whitespace) {
+            Some((header, args)) => (header, args.trim()),
+            None => (command, ""),
+        };
+        let header = header.to_ascii_uppercase();
+        let args = args
+            .split(',')
+            .map(|a| a.trim())
+            .filter(|a| !a.is_empty())
+            .collect::<Vec<&str>>();
+
+        self.advance_lists();
+        self.advance_timer();
+        if let Err((code, message)) = self.dispatch(&header, &args) {
+            self.push_error(code, message);
+        }
+        self.check_protection();
+    }
+
+    /// Switches the outputs off once the output timer has expired.
+    fn advance_timer(&mut self) {
+        if let Some(since) = self.output_since {
+            if self.timer_enabled && since.elapsed() >= self.timer_delay {
+                self.output = false;
+                self.output_since = None;
+            }
+        }
+    }
+
+    /// Moves running lists to the step due now, stopping them once all repeats are done.
+    fn advance_lists(&mut self) {
+        for c in self.channels.iter_mut() {
+            let Some(started) = c.list_started else {
+                continue;
+            };
+            let cycle = c.list.steps.iter().map(|s| s.2).sum::<Duration>();
+            let mut elapsed = started.elapsed();
+            if cycle.is_zero() || elapsed >= cycle * c.list.count {
+                c.list_started = None;
+                if let Some(&(v, i, _)) = c.list.steps.last() {
+                    (c.voltage, c.current) = (v, i);
+                }
+                continue;
+            }
+            elapsed = Duration::from_nanos((elapsed.as_nanos() % cycle.as_nanos()) as u64);
+            for &(v, i, dwell) in &c.list.steps {
+                if elapsed < dwell {
+                    (c.voltage, c.current) = (v, i);
+                    break;
+                }
+                elapsed -= dwell;
+            }
+        }
+    }
+
+    /// Trips OVP on any live channel whose output would exceed its level.
+    fn check_protection(&mut self) {
+        for ch in [Channel::CH1, Channel::CH2, Channel::CH3] {
+            let (v, _, _) = self.reading(ch);
+            let c = &mut self.channels[index(ch)];
+            if c.ovp_enabled && v > c.ovp_level {
+                c.ovp_tripped = true;
+                c.enabled = false;
+            }
+        }
+    }
+
+    fn dispatch(
+        &mut self,
+        header: &str,
+        args: &[&str],
+    ) -> std::result::Result<(), (i32, &'static str)> {
+        match header {
+            "*IDN?" => self.respond(format!(
+                "Keithley instruments, {}, {}, {}",
+                self.model, SIM_SERIAL, SIM_FIRMWARE
+            )),
+            "*RST" => {
+                let loads = self.channels.clone().map(|c| c.load);
+                *self = Self {
+                    errors: std::mem::take(&mut self.errors),
+                    saved_lists: std::mem::take(&mut self.saved_lists),
+                    saved_setups: std::mem::take(&mut self.saved_setups),
+                    ..Self::new(self.model)
+                };
+                for (c, load) in self.channels.iter_mut().zip(loads) {
+                    c.load = load;
+                }
+            }
+            "*CLS" => self.errors.clear(),
+            "*SAV" => {
+                let slot = number_arg(args, 0, crate::MemorySlot::MAX.into())? as u8;
+                if slot < crate::MemorySlot::MIN {
+                    return Err((-222, "Data out of range"));
+                }
+                let setup = self
+                    .channels
+                    .iter()
+                    .map(|c| (c.voltage, c.current, c.ovp_level, c.ovp_enabled))
+                    .collect();
+                self.saved_setups.insert(slot, setup);
+            }
+            "*RCL" => {
+                let slot = number_arg(args, 0, crate::MemorySlot::MAX.into())? as u8;
+                let Some(setup) = self.saved_setups.get(&slot) else {
+                    return Err((-222, "Data out of range"));
+                };
+                for (c, &(v, i, ovp, ovp_enabled)) in self.channels.iter_mut().zip(setup) {
+                    (c.voltage, c.current, c.ovp_level, c.ovp_enabled) = (v, i, ovp, ovp_enabled);
+                }
+            }
+            "*OPC?" => self.respond("1".to_string()),
+            "SYST:ERR?" => {
+                let (code, message) = self
+                    .errors
+                    .pop_front()
+                    .unwrap_or((0, "No error".to_string()));
+                self.respond(format!("{},\"{}\"", code, message));
+            }
+            "SYST:LOC" => self.remote = false,
+            "SYST:REM" => self.remote = true,
+            "INST" => self.selected = self.channel_arg(args, 0)?,
+            "INST?" => self.respond(self.selected.to_string()),
+            "APPL" => {
+                let ch = self.channel_arg(args, 0)?;
+                let limits = self.limits(ch);
+                let v = number_arg(args, 1, limits.max_voltage)?;
+                let i = number_arg(args, 2, limits.max_current)?;
+                let c = &mut self.channels[index(ch)];
+                c.voltage = v;
+                c.current = i;
+            }
+            "APPL?" => {
+                let ch = match args.first() {
+                    Some(_) => self.channel_arg(args, 0)?,
+                    None => self.selected,
+                };
+                let c = &self.channels[index(ch)];
+                self.respond(format!("{:.3}, {:.3}", c.voltage, c.current));
+            }
+            "VOLT" => {
+                let max = self.limits(self.selected).max_voltage;
+                self.channels[index(self.selected)].voltage = number_arg(args, 0, max)?;
+            }
+            "CURR" => {
+                let max = self.limits(self.selected).max_current;
+                self.channels[index(self.selected)].current = number_arg(args, 0, max)?;
+            }
+            "VOLT?" => {
+                let voltage = self.channels[index(self.selected)].voltage;
+                self.respond(format!("{:.3}", voltage));
+            }
+            "CURR?" => {
+                let current = self.channels[index(self.selected)].current;
+                self.respond(format!("{:.3}", current));
+            }
+            "CHAN:OUTP" => {
+                let enable = state_arg(args, 0)?;
+                let c = &mut self.channels[index(self.selected)];
+                if enable && c.ovp_tripped {
+                    return Err((-221, "Settings conflict"));
+                }
+                c.enabled = enable;
+            }
+            h if h.starts_with("LIST:") && !self.model.features().list_mode => {
+                return Err((-113, "Undefined header"));
+            }
+            "LIST:STEP" => {
+                let n = number_arg(args, 0, crate::ListSequence::MAX_STEPS as f32)? as usize;
+                let list = &mut self.channels[index(self.selected)].list;
+                list.steps
+                    .resize(n, (0.0, 0.0, crate::ListSequence::MIN_DWELL));
+            }
+            "LIST:UNIT" => {
+                let limits = self.limits(self.selected);
+                let list = &mut self.channels[index(self.selected)].list;
+                let n = number_arg(args, 0, list.steps.len() as f32)? as usize;
+                let v = number_arg(args, 1, limits.max_voltage)?;
+                let i = number_arg(args, 2, limits.max_current)?;
+                let dwell = number_arg(args, 3, crate::ListSequence::MAX_DWELL.as_secs_f32())?;
+                if n == 0 {
+                    return Err((-222, "Data out of range"));
+                }
+                list.steps[n - 1] = (v, i, Duration::from_secs_f32(dwell));
+            }
+            "LIST:COUN" => {
+                let count = number_arg(args, 0, crate::ListSequence::MAX_REPEAT as f32)?;
+                self.channels[index(self.selected)].list.count = count as u32;
+            }
+            "LIST:SAVE" => {
+                let slot = number_arg(args, 0, crate::ListSlot::MAX.into())? as u8;
+                let list = self.channels[index(self.selected)].list.clone();
+                self.saved_lists.insert(slot, list);
+            }
+            "LIST:RCL" => {
+                let slot = number_arg(args, 0, crate::ListSlot::MAX.into())? as u8;
+                let list = self.saved_lists.get(&slot).cloned().unwrap_or_default();
+                self.channels[index(self.selected)].list = list;
+            }
+            "LIST:STAT" => {
+                let c = &mut self.channels[index(self.selected)];
+                if state_arg(args, 0)? {
+                    if c.list.steps.is_empty() {
+                        return Err((-221, "Settings conflict"));
+                    }
+                    c.list_started = Some(Instant::now());
+                } else {
+                    c.list_started = None;
+                }
+            }
+            "LIST:STAT?" => {
+                let running = self.channels[index(self.selected)].list_started.is_some();
+                self.respond(bool_response(running));
+            }
+            "VOLT:PROT" => {
+                let max = self.limits(self.selected).max_voltage * 1.1;
+                self.channels[index(self.selected)].ovp_level = number_arg(args, 0, max)?;
+            }
+            "VOLT:PROT?" => {
+                let level = self.channels[index(self.selected)].ovp_level;
+                self.respond(format!("{:.3}", level));
+            }
+            "VOLT:PROT:STAT" => {
+                self.channels[index(self.selected)].ovp_enabled = state_arg(args, 0)?
+            }
+            "VOLT:PROT:STAT?" => {
+                let enabled = self.channels[index(self.selected)].ovp_enabled;
+                self.respond(bool_response(enabled));
+            }
+            "VOLT:PROT:TRIP?" => {
+                let tripped = self.channels[index(self.selected)].ovp_tripped;
+                self.respond(bool_response(tripped));
+            }
+            "VOLT:PROT:CLE" => self.channels[index(self.selected)].ovp_tripped = false,
+            "OUTP:ENAB" => {
+                let enable = state_arg(args, 0)?;
+                if enable && !self.output {
+                    self.output_since = Some(Instant::now());
+                }
+                self.output = enable;
+            }
+            "OUTP:TIM:DEL" => {
+                let max = crate::OUTPUT_TIMER_MAX.as_secs_f32();
+                self.timer_delay = Duration::from_secs_f32(number_arg(args, 0, max)?);
+            }
+            "OUTP:TIM:DEL?" => self.respond(format!("{:.1}", self.timer_delay.as_secs_f32())),
+            "OUTP:TIM:STAT" => {
+                self.timer_enabled = state_arg(args, 0)?;
+                if self.output {
+                    self.output_since = Some(Instant::now());
+                }
+            }
+            "OUTP:TIM:STAT?" => self.respond(bool_response(self.timer_enabled)),
+            "OUTP:ENAB?" => self.respond(bool_response(self.output)),
+            "CHAN:OUTP?" => {
+                let enabled = self.channels[index(self.selected)].enabled;
+                self.respond(bool_response(enabled));
+            }
+            "OUT:PAR" => {
+                let enable = state_arg(args, 0)?;
+                if enable && self.series {
+                    return Err((-221, "Settings conflict"));
+                }
+                self.parallel = enable;
+            }
+            "OUT:SER" => {
+                let enable = state_arg(args, 0)?;
+                if enable && self.parallel {
+                    return Err((-221, "Settings conflict"));
+                }
+                self.series = enable;
+            }
+            "OUT:TRAC" => self.tracking = state_arg(args, 0)?,
+            "OUT:PAR?" => self.respond(bool_response(self.parallel)),
+            "OUT:SER?" => self.respond(bool_response(self.series)),
+            "OUT:TRAC?" => self.respond(bool_response(self.tracking)),
+            "FETC:VOLT?" | "FETC:CURR?" | "FETC:POW?" | "MEAS:VOLT?" | "MEAS:CURR?"
+            | "MEAS:POW?" => {
+                if args.first().map(|a| a.to_ascii_uppercase()) != Some("ALL".to_string()) {
+                    return Err((-109, "Missing parameter"));
+                }
+                let pick = match &header[5..] {
+                    "VOLT?" => |r: (f32, f32, f32)| r.0,
+                    "CURR?" => |r: (f32, f32, f32)| r.1,
+                    _ => |r: (f32, f32, f32)| r.2,
+                };
+                let values = self
+                    .limits
+                    .iter()
+                    .map(|l| format!("{:.3}", pick(self.reading(l.channel))))
+                    .collect::<Vec<String>>();
+                self.respond(values.join(", "));
+            }
+            _ => return Err((-113, "Undefined header")),
+        }
+        Ok(())
+    }
+
+    fn limits(&self, ch: Channel) -> ChannelLimits {
+        *self
+            .limits
+            .iter()
+            .find(|l| l.channel == ch)
+            .expect("channel validated by channel_arg")
+    }
+
+    fn channel_arg(
+        &self,
+        args: &[&str],
+        n: usize,
+    ) -> std::result::Result<Channel, (i32, &'static str)> {
+        let arg = args.get(n).ok_or((-109, "Missing parameter"))?;
+        match Channel::from_str(&arg.to_ascii_uppercase()) {
+            Ok(ch) if self.limits.iter().any(|l| l.channel == ch) => Ok(ch),
+            _ => Err((-224, "Illegal parameter value")),
+        }
+    }
+
+    fn respond(&mut self, response: String) {
+        self.responses.push_back(response);
+    }
+}
+
+impl Transport for Simulator {
+    fn write(&mut self, command: &str) -> Result<()> {
+        for line in command.lines() {
+            // Queries chained with ';' answer on a single ';' separated line.
+            let pending = self.responses.len();
+            for unit in line.split(';') {
+                self.execute(unit);
+            }
+            if self.responses.len() > pending + 1 {
+                let joined = self.responses.split_off(pending).into_iter();
+                self.respond(joined.collect::<Vec<String>>().join(";"));
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<String> {
+        match self.responses.pop_front() {
+            Some(response) => Ok(response),
+            None => {
+                self.push_error(-420, "Query UNTERMINATED");
+                Err(Error::Timeout())
+            }
+        }
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.responses.clear();
+        Ok(())
+    }
+}
+
+impl Reconnect for Simulator {
+    /// A fresh session loses any unread responses but keeps instrument state.
+    fn reconnect(&mut self) -> Result<()> {
+        self.responses.clear();
+        Ok(())
+    }
+}
+
+fn index(ch: Channel) -> usize {
+    match ch {
+        Channel::CH1 => 0,
+        Channel::CH2 => 1,
+        Channel::CH3 => 2,
+    }
+}
+
+fn bool_response(value: bool) -> String {
+    if value { "1" } else { "0" }.to_string()
+}
+
+fn number_arg(args: &[&str], n: usize, max: f32) -> std::result::Result<f32, (i32, &'static str)> {
+    let arg = args.get(n).ok_or((-109, "Missing parameter"))?;
+    match arg.parse::<f32>() {
+        Ok(value) if value.is_finite() && (0.0..=max).contains(&value) => Ok(value),
+        Ok(_) => Err((-222, "Data out of range")),
+        Err(_) => Err((-104, "Data type error")),
+    }
+}
+
+fn state_arg(args: &[&str], n: usize) -> std::result::Result<bool, (i32, &'static str)> {
+    let arg = args.get(n).ok_or((-109, "Missing parameter"))?;
+    match arg.to_ascii_uppercase().as_str() {
+        "ON" | "1" => Ok(true),
+        "OFF" | "0" => Ok(false),
+        _ => Err((-224, "Illegal parameter value")),
+    }
+}
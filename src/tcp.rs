@@ -0,0 +1,167 @@
+use crate::transport::map_io;
+use crate::{Error, Reconnect, Result, Transport};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// SCPI over a plain TCP socket, with newline terminated commands and responses.
+#[derive(Debug)]
+pub struct TcpTransport {
+    stream: TcpStream,
+    addr: SocketAddr,
+    timeout: Duration,
+    /// Bytes received past the last complete line.
+    pending: Vec<u8>,
+}
+
+impl TcpTransport {
+    /// Raw SCPI socket port used by the 2230 LAN interface.
+    pub const DEFAULT_PORT: u16 = 5025;
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+    pub fn connect<A: ToSocketAddrs>(addr: A, timeout: Duration) -> Result<Self> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(timeout))?;
+                    stream.set_write_timeout(Some(timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(Self {
+                        stream,
+                        addr,
+                        timeout,
+                        pending: Vec::new(),
+                    });
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.map(map_io).unwrap_or(Error::NoInstrumentFound()))
+    }
+
+    /// Like [`connect`](Self::connect), but `addr` may leave out the port, in
+    /// which case [`DEFAULT_PORT`](Self::DEFAULT_PORT) is used.
+    pub fn connect_host(addr: &str, timeout: Duration) -> Result<Self> {
+        Self::connect(with_default_port(addr), timeout)
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let stream = &self.stream;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        self.timeout = timeout;
+        Ok(())
+    }
+}
+
+impl Transport for TcpTransport {
+    fn write(&mut self, command: &str) -> Result<()> {
+        self.stream
+            .write_all(format!("{}\n", command).as_bytes())
+            .map_err(map_io)?;
+        self.stream.flush().map_err(map_io)?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<String> {
+        let mut chunk = [0u8; 256];
+        loop {
+            if let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+                let line = self.pending.drain(..=end).collect::<Vec<u8>>();
+                let line = String::from_utf8_lossy(&line);
+                return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+            }
+            // On timeout the partial line stays in `pending` for the next call.
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(Error::Io(ErrorKind::UnexpectedEof.into())),
+                Ok(n) => self.pending.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(map_io(e)),
+            }
+        }
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        // Drop whatever is buffered locally, then drain anything still in flight.
+        self.pending.clear();
+        let stream = &mut self.stream;
+        stream.set_nonblocking(true)?;
+        let mut scratch = [0u8; 256];
+        let drained = loop {
+            match stream.read(&mut scratch) {
+                Ok(0) => break Ok(()),
+                Ok(_) => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        stream.set_nonblocking(false)?;
+        drained?;
+        Ok(())
+    }
+}
+
+/// Appends [`TcpTransport::DEFAULT_PORT`] to a bare host name or IP address.
+fn with_default_port(addr: &str) -> String {
+    if addr.parse::<SocketAddr>().is_ok() {
+        return addr.to_string();
+    }
+    if let Ok(ip) = addr.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return SocketAddr::new(ip, TcpTransport::DEFAULT_PORT).to_string();
+    }
+    match addr.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => addr.to_string(),
+        _ => format!("{}:{}", addr, TcpTransport::DEFAULT_PORT),
+    }
+}
+
+impl Reconnect for TcpTransport {
+    fn reconnect(&mut self) -> Result<()> {
+        *self = Self::connect(self.addr, self.timeout)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn default_port_is_added_only_when_missing() {
+        assert_eq!(with_default_port("10.0.0.5"), "10.0.0.5:5025");
+        assert_eq!(with_default_port("10.0.0.5:99"), "10.0.0.5:99");
+        assert_eq!(with_default_port("::1"), "[::1]:5025");
+        assert_eq!(with_default_port("[::1]:99"), "[::1]:99");
+        assert_eq!(with_default_port("psu.lab"), "psu.lab:5025");
+        assert_eq!(with_default_port("psu.lab:99"), "psu.lab:99");
+    }
+
+    #[test]
+    fn partial_line_survives_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut peer, _) = listener.accept().unwrap();
+            peer.write_all(b"1.5,2.").unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            peer.write_all(b"5,3.5\nCH2\n").unwrap();
+        });
+
+        let mut t = TcpTransport::connect(addr, Duration::from_millis(100)).unwrap();
+        assert!(matches!(t.read(), Err(Error::Timeout())));
+        t.set_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(t.read().unwrap(), "1.5,2.5,3.5");
+        assert_eq!(t.read().unwrap(), "CH2");
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{Error, Result};
+use std::io::ErrorKind;
+
+/// Byte-level link to an instrument that speaks line-terminated SCPI.
+pub trait Transport {
+    fn write(&mut self, command: &str) -> Result<()>;
+
+    /// Reads one response line with the terminator stripped.
+    fn read(&mut self) -> Result<String>;
+
+    fn query(&mut self, command: &str) -> Result<String> {
+        self.write(command)?;
+        self.read()
+    }
+
+    /// Discards any pending input/output and resets the interface.
+    fn clear(&mut self) -> Result<()>;
+}
+
+#[cfg(feature = "visa")]
+impl Transport for visa_api::Instrument {
+    fn write(&mut self, command: &str) -> Result<()> {
+        visa_api::Visa::write(self, command)?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<String> {
+        let response = visa_api::Visa::read(self)?;
+        Ok(response.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        visa_api::Instrument::clear(self).map_err(visa_api::Error::from)?;
+        Ok(())
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for &mut T {
+    fn write(&mut self, command: &str) -> Result<()> {
+        (**self).write(command)
+    }
+
+    fn read(&mut self) -> Result<String> {
+        (**self).read()
+    }
+
+    fn query(&mut self, command: &str) -> Result<String> {
+        (**self).query(command)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        (**self).clear()
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn write(&mut self, command: &str) -> Result<()> {
+        (**self).write(command)
+    }
+
+    fn read(&mut self) -> Result<String> {
+        (**self).read()
+    }
+
+    fn query(&mut self, command: &str) -> Result<String> {
+        (**self).query(command)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        (**self).clear()
+    }
+}
+
+/// Maps socket/port read timeouts to [`Error::Timeout`].
+pub(crate) fn map_io(e: std::io::Error) -> Error {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => Error::Timeout(),
+        _ => Error::Io(e),
+    }
+}